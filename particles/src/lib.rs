@@ -2,6 +2,12 @@ use macroquad::prelude::*;
 use macroquad::window::miniquad::*;
 use miniquad::graphics::RenderPass;
 
+use std::rc::Rc;
+
+mod trail;
+
+pub use trail::{Trail, TrailConfig};
+
 #[cfg(feature = "nanoserde")]
 use nanoserde::{DeJson, SerJson};
 
@@ -232,10 +238,28 @@ pub struct EmitterConfig {
     #[cfg_attr(feature = "nanoserde", nserde(proxy = "Vec2Serializable"))]
     pub gravity: Vec2,
 
+    /// Bounce particles off the grid given to `Emitter::set_collision_grid`.
+    /// If none particles fly through everything.
+    #[cfg_attr(feature = "nanoserde", nserde(default))]
+    pub collision: Option<CollisionConfig>,
+
+    /// Emitter spawned where a particle dies or collides, depending on `sub_emitter_trigger`.
+    /// Good for fireworks: a rocket bursting into sparks.
+    /// Sub-emitters are always one shot, and nest at most `EmittersCache::MAX_SUB_EMITTER_DEPTH` levels deep.
+    #[cfg_attr(feature = "nanoserde", nserde(default))]
+    pub sub_emitter: Option<Box<EmitterConfig>>,
+    #[cfg_attr(feature = "nanoserde", nserde(default))]
+    pub sub_emitter_trigger: SubEmitterTrigger,
+
     /// Particle texture. If none particles going to be white squares.
     #[cfg_attr(feature = "nanoserde", nserde(skip))]
     pub texture: Option<Texture2D>,
 
+    /// Where `texture` comes from. Unlike the texture itself it is serialized,
+    /// use `resolve_textures` to set the texture back after loading the config.
+    #[cfg_attr(feature = "nanoserde", nserde(default))]
+    pub texture_path: Option<String>,
+
     /// For animated texture specify spritesheet layout.
     /// If none the whole texture will be used.
     pub atlas: Option<AtlasConfig>,
@@ -251,6 +275,64 @@ pub struct EmitterConfig {
     pub post_processing: Option<PostProcessing>,
 }
 
+impl EmitterConfig {
+    /// Set `texture` from `texture_path`, with the texture `textures` returns for that path,
+    /// for this config and its `sub_emitter`s.
+    /// Returns false if there is a path but no texture for it.
+    ///
+    /// ```ignore
+    /// let mut config: EmitterConfig = DeJson::deserialize_json(&load_string("fire.json").await?)?;
+    /// let fire = load_texture("fire.png").await?;
+    /// config.resolve_textures(|path| (path == "fire.png").then(|| fire.clone()));
+    /// ```
+    pub fn resolve_textures(
+        &mut self,
+        mut textures: impl FnMut(&str) -> Option<Texture2D>,
+    ) -> bool {
+        self.resolve_textures_with(&mut textures)
+    }
+
+    fn resolve_textures_with(
+        &mut self,
+        textures: &mut dyn FnMut(&str) -> Option<Texture2D>,
+    ) -> bool {
+        let resolved = match &self.texture_path {
+            Some(path) => {
+                self.texture = textures(path);
+                self.texture.is_some()
+            }
+            None => true,
+        };
+        let sub_resolved = match &mut self.sub_emitter {
+            Some(sub_emitter) => sub_emitter.resolve_textures_with(textures),
+            None => true,
+        };
+
+        resolved && sub_resolved
+    }
+}
+
+#[test]
+fn sub_emitter_textures_are_resolved() {
+    let mut config = EmitterConfig {
+        texture_path: Some("fire.png".to_string()),
+        sub_emitter: Some(Box::new(EmitterConfig {
+            texture_path: Some("smoke.png".to_string()),
+            ..Default::default()
+        })),
+        ..Default::default()
+    };
+
+    let mut requested = vec![];
+    let resolved = config.resolve_textures(|path| {
+        requested.push(path.to_string());
+        None
+    });
+
+    assert!(!resolved);
+    assert_eq!(requested, ["fire.png", "smoke.png"]);
+}
+
 impl EmissionShape {
     fn gen_random_point(&self) -> Vec2 {
         match self {
@@ -259,12 +341,7 @@ impl EmissionShape {
                 rand::gen_range(-width / 2., width / 2.0),
                 rand::gen_range(-height / 2., height / 2.0),
             ),
-            EmissionShape::Sphere { radius } => {
-                let ro = rand::gen_range(0., radius * radius).sqrt();
-                let phi = rand::gen_range(0., std::f32::consts::PI * 2.);
-
-                macroquad::math::polar_to_cartesian(ro, phi)
-            }
+            EmissionShape::Sphere { radius } => rand::sample_in_circle(*radius),
         }
     }
 }
@@ -453,6 +530,30 @@ impl AtlasConfig {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "nanoserde", derive(DeJson, SerJson))]
+pub struct CollisionConfig {
+    /// Part of the velocity kept after hitting something.
+    /// 0.0 makes particles stop, 1.0 bounce without losing speed.
+    pub bounce: f32,
+}
+
+impl Default for CollisionConfig {
+    fn default() -> CollisionConfig {
+        CollisionConfig { bounce: 0.5 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "nanoserde", derive(DeJson, SerJson))]
+pub enum SubEmitterTrigger {
+    /// When a particle reaches the end of its lifetime.
+    #[default]
+    OnDeath,
+    /// When a particle bounces off the collision grid, see `EmitterConfig::collision`.
+    OnCollision,
+}
+
 impl Default for EmitterConfig {
     fn default() -> EmitterConfig {
         EmitterConfig {
@@ -482,7 +583,11 @@ impl Default for EmitterConfig {
             blend_mode: BlendMode::Alpha,
             colors_curve: ColorCurve::default(),
             gravity: vec2(0.0, 0.0),
+            collision: None,
+            sub_emitter: None,
+            sub_emitter_trigger: SubEmitterTrigger::OnDeath,
             texture: None,
+            texture_path: None,
             atlas: None,
             material: None,
             post_processing: None,
@@ -529,6 +634,14 @@ pub struct Emitter {
     blend_mode: BlendMode,
     mesh_dirty: bool,
 
+    collision_grid: Option<Rc<dyn Fn(Vec2) -> bool>>,
+
+    // nesting level of sub-emitters, 0 for the top level emitter
+    depth: usize,
+    // where sub-emitters should be spawned, filled by update
+    sub_emissions: Vec<Vec2>,
+    sub_emitters: Option<Box<EmittersCache>>,
+
     pub config: EmitterConfig,
 }
 
@@ -698,12 +811,17 @@ impl Emitter {
             time_passed: 0.0,
             particles_current_cycle: 0,
             mesh_dirty: false,
+            collision_grid: None,
+            depth: 0,
+            sub_emissions: vec![],
+            sub_emitters: None,
         }
     }
 
     fn reset(&mut self) {
         self.gpu_particles.clear();
         self.cpu_counterpart.clear();
+        self.sub_emissions.clear();
         self.last_emit_time = 0.0;
         self.time_passed = 0.0;
         self.particles_spawned = 0;
@@ -717,6 +835,18 @@ impl Emitter {
         self.mesh_dirty = true;
     }
 
+    /// Tell which points, in the camera coordinate system, are solid.
+    /// Only used when `config.collision` is set.
+    ///
+    /// ```ignore
+    /// emitter.set_collision_grid(move |pos| {
+    ///     map.solid_at((pos.x / TILE_SIZE) as i32, (pos.y / TILE_SIZE) as i32)
+    /// });
+    /// ```
+    pub fn set_collision_grid(&mut self, grid: impl Fn(Vec2) -> bool + 'static) {
+        self.collision_grid = Some(Rc::new(grid));
+    }
+
     fn emit_particle(&mut self, offset: Vec2) {
         let offset = offset + self.config.emission_shape.gen_random_point();
 
@@ -826,6 +956,13 @@ impl Emitter {
             self.config.emitting = false;
         }
 
+        let sub_emitter_trigger = sub_emitter_trigger(&self.config, self.depth);
+        let origin = if self.config.local_coords {
+            self.position
+        } else {
+            vec2(0.0, 0.0)
+        };
+
         for (gpu, cpu) in self.gpu_particles.iter_mut().zip(&mut self.cpu_counterpart) {
             // TODO: this is not quite the way to apply acceleration, this is not
             // fps independent and just wrong
@@ -845,6 +982,31 @@ impl Emitter {
                         + self.config.colors_curve.end.to_vec() * t
                 }
             };
+
+            if let (Some(collision), Some(grid)) = (&self.config.collision, &self.collision_grid) {
+                let pos = origin + vec2(gpu.pos.x, gpu.pos.y);
+                let step = cpu.velocity * dt;
+
+                if grid(pos + step) {
+                    // point test each axis to find out which way the surface is facing
+                    let hit_x = grid(pos + vec2(step.x, 0.0));
+                    let hit_y = grid(pos + vec2(0.0, step.y));
+                    if hit_x {
+                        cpu.velocity.x = -cpu.velocity.x * collision.bounce;
+                    }
+                    if hit_y {
+                        cpu.velocity.y = -cpu.velocity.y * collision.bounce;
+                    }
+                    if !hit_x && !hit_y {
+                        // exactly into a corner
+                        cpu.velocity = -cpu.velocity * collision.bounce;
+                    }
+
+                    if sub_emitter_trigger == Some(SubEmitterTrigger::OnCollision) {
+                        self.sub_emissions.push(pos);
+                    }
+                }
+            }
             gpu.pos += vec4(cpu.velocity.x, cpu.velocity.y, cpu.angular_velocity, 0.0) * dt;
 
             gpu.pos.w = cpu.initial_size
@@ -883,19 +1045,14 @@ impl Emitter {
             }
         }
 
-        for i in (0..self.gpu_particles.len()).rev() {
-            // second if clause is just for the case when lifetime was changed in the editor
-            // normally particle lifetime is always less or equal config lifetime
-            if self.cpu_counterpart[i].lived >= self.cpu_counterpart[i].lifetime
-                || self.cpu_counterpart[i].lived > self.config.lifetime
-            {
-                if self.cpu_counterpart[i].lived != self.cpu_counterpart[i].lifetime {
-                    self.particles_spawned -= 1;
-                }
-                self.gpu_particles.remove(i);
-                self.cpu_counterpart.remove(i);
-            }
-        }
+        self.particles_spawned -= remove_dead_particles(
+            &mut self.gpu_particles,
+            &mut self.cpu_counterpart,
+            self.config.lifetime,
+            origin,
+            sub_emitter_trigger == Some(SubEmitterTrigger::OnDeath),
+            &mut self.sub_emissions,
+        );
 
         ctx.buffer_update(
             self.bindings.vertex_buffers[1],
@@ -996,9 +1153,175 @@ impl Emitter {
         self.setup_render_pass(quad_gl, ctx);
         self.perform_render_pass(quad_gl, ctx);
         self.end_render_pass(quad_gl, ctx);
+
+        spawn_sub_emitters(
+            &mut self.sub_emitters,
+            &self.config,
+            self.depth,
+            &self.collision_grid,
+            &mut self.sub_emissions,
+        );
+        if let Some(sub_emitters) = &mut self.sub_emitters {
+            sub_emitters.draw();
+        }
+    }
+}
+
+/// Drop the particles past their lifetime, recording where they died into
+/// `sub_emissions` if `on_death`.
+/// Returns how many of them lived past their exact lifetime.
+fn remove_dead_particles(
+    gpu_particles: &mut Vec<GpuParticle>,
+    cpu_counterpart: &mut Vec<CpuParticle>,
+    max_lifetime: f32,
+    origin: Vec2,
+    on_death: bool,
+    sub_emissions: &mut Vec<Vec2>,
+) -> u64 {
+    let mut overlived = 0;
+    for i in (0..gpu_particles.len()).rev() {
+        // second if clause is just for the case when lifetime was changed in the editor
+        // normally particle lifetime is always less or equal config lifetime
+        if cpu_counterpart[i].lived >= cpu_counterpart[i].lifetime
+            || cpu_counterpart[i].lived > max_lifetime
+        {
+            if cpu_counterpart[i].lived != cpu_counterpart[i].lifetime {
+                overlived += 1;
+            }
+            if on_death {
+                let pos = gpu_particles[i].pos;
+                sub_emissions.push(origin + vec2(pos.x, pos.y));
+            }
+            gpu_particles.remove(i);
+            cpu_counterpart.remove(i);
+        }
+    }
+    overlived
+}
+
+/// What spawns a sub-emitter for an emitter `depth` levels deep, if anything.
+fn sub_emitter_trigger(config: &EmitterConfig, depth: usize) -> Option<SubEmitterTrigger> {
+    if config.sub_emitter.is_some() && depth + 1 < EmittersCache::MAX_SUB_EMITTER_DEPTH {
+        Some(config.sub_emitter_trigger)
+    } else {
+        None
+    }
+}
+
+fn spawn_sub_emitters(
+    sub_emitters: &mut Option<Box<EmittersCache>>,
+    config: &EmitterConfig,
+    depth: usize,
+    collision_grid: &Option<Rc<dyn Fn(Vec2) -> bool>>,
+    positions: &mut Vec<Vec2>,
+) {
+    if positions.is_empty() {
+        return;
+    }
+
+    let sub_emitter = match &config.sub_emitter {
+        Some(sub_emitter) => sub_emitter,
+        // the sub-emitter was removed from the config after these were recorded
+        None => {
+            positions.clear();
+            return;
+        }
+    };
+    let sub_emitters = sub_emitters.get_or_insert_with(|| {
+        Box::new(EmittersCache::with_depth(
+            EmitterConfig {
+                one_shot: true,
+                ..(**sub_emitter).clone()
+            },
+            depth + 1,
+        ))
+    });
+    // sparks from a collision should bounce off the same walls
+    sub_emitters.collision_grid = collision_grid.clone();
+    for pos in positions.drain(..) {
+        sub_emitters.spawn(pos);
     }
 }
 
+#[test]
+fn sub_emitters_nest_up_to_max_depth() {
+    let config = EmitterConfig {
+        sub_emitter: Some(Box::new(EmitterConfig::default())),
+        sub_emitter_trigger: SubEmitterTrigger::OnCollision,
+        ..Default::default()
+    };
+    let max = EmittersCache::MAX_SUB_EMITTER_DEPTH;
+
+    assert_eq!(
+        sub_emitter_trigger(&config, 0),
+        Some(SubEmitterTrigger::OnCollision)
+    );
+    assert_eq!(
+        sub_emitter_trigger(&config, max - 2),
+        Some(SubEmitterTrigger::OnCollision)
+    );
+    assert_eq!(sub_emitter_trigger(&config, max - 1), None);
+    assert_eq!(sub_emitter_trigger(&EmitterConfig::default(), 0), None);
+}
+
+#[test]
+fn sub_emissions_without_a_sub_emitter_are_dropped() {
+    let mut sub_emitters = None;
+    let mut positions = vec![vec2(1.0, 2.0)];
+    spawn_sub_emitters(
+        &mut sub_emitters,
+        &EmitterConfig::default(),
+        0,
+        &None,
+        &mut positions,
+    );
+
+    assert!(sub_emitters.is_none());
+    assert!(positions.is_empty());
+}
+
+#[test]
+fn sub_emissions_are_recorded_where_particles_die() {
+    let particle = |x: f32, lived: f32| {
+        (
+            GpuParticle {
+                pos: vec4(x, 1.0, 0.0, 1.0),
+                uv: Vec4::ZERO,
+                data: Vec4::ZERO,
+                color: Vec4::ONE,
+            },
+            CpuParticle {
+                velocity: Vec2::ZERO,
+                angular_velocity: 0.0,
+                lived,
+                lifetime: 1.0,
+                frame: 0,
+                initial_size: 1.0,
+            },
+        )
+    };
+    let (mut gpu, mut cpu): (Vec<_>, Vec<_>) =
+        vec![particle(1.0, 1.5), particle(2.0, 0.5), particle(3.0, 1.0)]
+            .into_iter()
+            .unzip();
+    let mut sub_emissions = vec![];
+
+    let origin = vec2(10.0, 20.0);
+    let overlived =
+        remove_dead_particles(&mut gpu, &mut cpu, 1.0, origin, true, &mut sub_emissions);
+
+    assert_eq!(overlived, 1);
+    assert_eq!(gpu.len(), 1);
+    assert_eq!(gpu[0].pos.x, 2.0);
+    assert_eq!(sub_emissions, [vec2(13.0, 21.0), vec2(11.0, 21.0)]);
+
+    // without a death trigger nothing is recorded
+    cpu[0].lived = 2.0;
+    remove_dead_particles(&mut gpu, &mut cpu, 1.0, origin, false, &mut sub_emissions);
+    assert!(gpu.is_empty());
+    assert_eq!(sub_emissions.len(), 2);
+}
+
 /// Multiple emitters drawn simultaneously.
 /// Will reuse as much GPU resources as possible, so should be more efficient than
 /// just Vec<Emitter>
@@ -1007,12 +1330,23 @@ pub struct EmittersCache {
     emitters_cache: Vec<Emitter>,
     active_emitters: Vec<Option<(Emitter, Vec2)>>,
     config: EmitterConfig,
+    collision_grid: Option<Rc<dyn Fn(Vec2) -> bool>>,
+    depth: usize,
+    sub_emitters: Option<Box<EmittersCache>>,
 }
 
 impl EmittersCache {
     const CACHE_DEFAULT_SIZE: usize = 10;
 
+    /// Sub-emitters of sub-emitters deeper than that never spawn, so a config
+    /// that contains itself can't spawn emitters forever.
+    pub const MAX_SUB_EMITTER_DEPTH: usize = 4;
+
     pub fn new(config: EmitterConfig) -> EmittersCache {
+        Self::with_depth(config, 0)
+    }
+
+    fn with_depth(config: EmitterConfig, depth: usize) -> EmittersCache {
         let mut emitters_cache = vec![];
         // prepopulate cache
         for _ in 0..Self::CACHE_DEFAULT_SIZE {
@@ -1026,9 +1360,26 @@ impl EmittersCache {
             emitters_cache,
             active_emitters: vec![],
             config,
+            collision_grid: None,
+            depth,
+            sub_emitters: None,
         }
     }
 
+    /// Same as `Emitter::set_collision_grid`, for every emitter spawned by this cache.
+    pub fn set_collision_grid(&mut self, grid: impl Fn(Vec2) -> bool + 'static) {
+        let grid: Rc<dyn Fn(Vec2) -> bool> = Rc::new(grid);
+        for emitter in self.emitters_cache.iter_mut().chain(
+            self.active_emitters
+                .iter_mut()
+                .flatten()
+                .map(|(emitter, _)| emitter),
+        ) {
+            emitter.collision_grid = Some(grid.clone());
+        }
+        self.collision_grid = Some(grid);
+    }
+
     pub fn spawn(&mut self, pos: Vec2) {
         let mut emitter = if let Some(emitter) = self.emitters_cache.pop() {
             emitter
@@ -1036,6 +1387,8 @@ impl EmittersCache {
             Emitter::new(self.config.clone())
         };
 
+        emitter.collision_grid = self.collision_grid.clone();
+        emitter.depth = self.depth;
         emitter.mesh_dirty = true;
         emitter.config.emitting = true;
         emitter.reset();
@@ -1043,6 +1396,12 @@ impl EmittersCache {
         self.active_emitters.push(Some((emitter, pos)));
     }
 
+    /// Update and draw all the spawned emitters.
+    ///
+    /// An emitter goes back to the cache once it stopped emitting and its last
+    /// particle died. One shot emitters used to be recycled as soon as they were
+    /// done emitting, cutting their particles short and never letting them die,
+    /// which `SubEmitterTrigger::OnDeath` relies on.
     pub fn draw(&mut self) {
         let mut gl = unsafe { get_internal_gl() };
 
@@ -1064,7 +1423,15 @@ impl EmittersCache {
 
                 emitter.perform_render_pass(quad_gl, ctx);
 
-                if emitter.config.emitting == false {
+                spawn_sub_emitters(
+                    &mut self.sub_emitters,
+                    &self.config,
+                    self.depth,
+                    &self.collision_grid,
+                    &mut emitter.sub_emissions,
+                );
+
+                if emitter.config.emitting == false && emitter.gpu_particles.is_empty() {
                     self.emitters_cache.push(i.take().unwrap().0);
                 }
             }
@@ -1073,7 +1440,11 @@ impl EmittersCache {
             self.emitter.end_render_pass(quad_gl, ctx);
         }
 
-        self.active_emitters.retain(|emitter| emitter.is_some())
+        self.active_emitters.retain(|emitter| emitter.is_some());
+
+        if let Some(sub_emitters) = &mut self.sub_emitters {
+            sub_emitters.draw();
+        }
     }
 }
 
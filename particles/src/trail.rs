@@ -0,0 +1,172 @@
+//! Motion trails: a ribbon following a moving point, for bullets, swords and cursors.
+
+use macroquad::prelude::*;
+
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone)]
+pub struct TrailConfig {
+    /// How long, in seconds, each recorded point stays in the trail.
+    pub lifetime: f32,
+    /// Recorded points limit, the oldest ones are dropped first.
+    /// A trail is drawn in a single draw call, so this is capped at
+    /// [Trail::MAX_POINTS].
+    pub max_points: usize,
+    /// A point is recorded only after moving that far from the previous one,
+    /// so a stationary object does not pile up zero length segments.
+    pub min_distance: f32,
+    /// Ribbon width at the head.
+    pub width: f32,
+    /// Ribbon width at the very end of the tail.
+    pub end_width: f32,
+    /// Color at the head.
+    pub color: Color,
+    /// Color at the very end of the tail, usually `color` with zero alpha.
+    pub end_color: Color,
+    /// Stretched along the trail, u goes from 0 at the head to 1 at the tail.
+    /// If none the trail is a solid color.
+    pub texture: Option<Texture2D>,
+}
+
+impl Default for TrailConfig {
+    fn default() -> TrailConfig {
+        TrailConfig {
+            lifetime: 0.3,
+            max_points: 64,
+            min_distance: 2.0,
+            width: 8.0,
+            end_width: 0.0,
+            color: WHITE,
+            end_color: Color::new(1.0, 1.0, 1.0, 0.0),
+            texture: None,
+        }
+    }
+}
+
+/// Tapering ribbon through the recent positions of a moving point.
+///
+/// ```ignore
+/// let mut trail = Trail::new(TrailConfig::default());
+/// loop {
+///     trail.update(bullet.pos, get_frame_time());
+///     trail.draw();
+///     next_frame().await;
+/// }
+/// ```
+pub struct Trail {
+    pub config: TrailConfig,
+    head: Option<Vec2>,
+    // newest first, with the time since each point was recorded
+    points: VecDeque<(Vec2, f32)>,
+}
+
+impl Trail {
+    /// Most points a trail keeps, whatever `TrailConfig::max_points` says.
+    /// Each segment takes 6 indices and the whole ribbon, head included,
+    /// has to fit in the 5000 indices of a default draw call.
+    pub const MAX_POINTS: usize = 833;
+
+    pub fn new(config: TrailConfig) -> Trail {
+        Trail {
+            config,
+            head: None,
+            points: VecDeque::new(),
+        }
+    }
+
+    /// Move the head of the trail to `pos` and age the recorded points by `dt`.
+    pub fn update(&mut self, pos: Vec2, dt: f32) {
+        for (_, age) in &mut self.points {
+            *age += dt;
+        }
+        while self
+            .points
+            .back()
+            .is_some_and(|(_, age)| *age > self.config.lifetime)
+        {
+            self.points.pop_back();
+        }
+
+        let last = self.points.front().map(|(point, _)| *point).or(self.head);
+        if !last.is_some_and(|last| last.distance(pos) < self.config.min_distance) {
+            self.points.push_front((pos, 0.0));
+            self.points
+                .truncate(self.config.max_points.min(Trail::MAX_POINTS));
+        }
+        self.head = Some(pos);
+    }
+
+    /// Forget all the recorded points, for example after a teleport.
+    pub fn clear(&mut self) {
+        self.head = None;
+        self.points.clear();
+    }
+
+    pub fn draw(&self) {
+        let head = match self.head {
+            Some(head) => head,
+            None => return,
+        };
+        let points = std::iter::once((head, 0.0))
+            .chain(
+                self.points
+                    .iter()
+                    .copied()
+                    .skip_while(|(point, _)| *point == head)
+                    .take(Trail::MAX_POINTS),
+            )
+            .collect::<Vec<_>>();
+        if points.len() < 2 {
+            return;
+        }
+
+        let mut vertices = Vec::with_capacity(points.len() * 2);
+        let mut indices = Vec::with_capacity((points.len() - 1) * 6);
+        for (i, (point, age)) in points.iter().enumerate() {
+            let previous = points[i.saturating_sub(1)].0;
+            let next = points[(i + 1).min(points.len() - 1)].0;
+            let normal = (previous - next).normalize_or_zero().perp();
+
+            let t = if self.config.lifetime > 0.0 {
+                (age / self.config.lifetime).min(1.0)
+            } else {
+                i as f32 / (points.len() - 1) as f32
+            };
+            let width = self.config.width + (self.config.end_width - self.config.width) * t;
+            let color = Color::from_vec(
+                self.config.color.to_vec()
+                    + (self.config.end_color.to_vec() - self.config.color.to_vec()) * t,
+            );
+
+            let u = i as f32 / (points.len() - 1) as f32;
+            let left = *point + normal * width / 2.0;
+            let right = *point - normal * width / 2.0;
+            vertices.push(Vertex::new(left.x, left.y, 0.0, u, 0.0, color));
+            vertices.push(Vertex::new(right.x, right.y, 0.0, u, 1.0, color));
+
+            if i + 1 < points.len() {
+                let i = i as u16 * 2;
+                indices.extend_from_slice(&[i, i + 1, i + 2, i + 1, i + 3, i + 2]);
+            }
+        }
+
+        let gl = unsafe { get_internal_gl() }.quad_gl;
+        gl.texture(self.config.texture.as_ref());
+        gl.draw_mode(DrawMode::Triangles);
+        gl.geometry(&vertices, &indices);
+    }
+}
+
+#[test]
+fn points_are_capped_to_one_draw_call() {
+    let mut trail = Trail::new(TrailConfig {
+        lifetime: 100.0,
+        max_points: 100_000,
+        ..Default::default()
+    });
+    for i in 0..2000 {
+        trail.update(vec2(i as f32 * 10.0, 0.0), 0.01);
+    }
+
+    assert_eq!(trail.points.len(), Trail::MAX_POINTS);
+}
@@ -2,6 +2,12 @@ use macroquad::math::{vec2, Rect, Vec2};
 
 use std::collections::HashSet;
 
+mod pathfind;
+mod visibility;
+
+pub use pathfind::{find_path, Heuristic, PathfindParams};
+pub use visibility::visibility_polygon;
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Tile {
     Empty,
@@ -322,8 +328,8 @@ impl World {
             tag: layer_tag,
         } in &self.static_tiled_layers
         {
-            let y = (pos.y / tile_width) as i32;
-            let x = (pos.x / tile_height) as i32;
+            let y = (pos.y / tile_height) as i32;
+            let x = (pos.x / tile_width) as i32;
             let ix = y * (*width as i32) + x;
 
             if ix >= 0
@@ -370,8 +376,8 @@ impl World {
         {
             let layer_height = static_colliders.len() / layer_width + 1;
             let check = |pos: Vec2| {
-                let y = (pos.y / tile_width) as i32;
-                let x = (pos.x / tile_height) as i32;
+                let y = (pos.y / tile_height) as i32;
+                let x = (pos.x / tile_width) as i32;
                 let ix = y * (*layer_width as i32) + x;
                 if y >= 0
                     && y < layer_height as i32
@@ -428,6 +434,46 @@ impl World {
         return Tile::Empty;
     }
 
+    /// A* over the static tiled layers, see [find_path].
+    ///
+    /// The grid size comes from the first layer, all layers are expected to
+    /// share it. A tile is the first non-empty one across the layers, and
+    /// `passable` decides if it can be walked through, `|tile| tile == Tile::Empty`
+    /// for a top-down game.
+    pub fn find_path(
+        &self,
+        start: (u32, u32),
+        goal: (u32, u32),
+        passable: impl Fn(Tile) -> bool,
+        params: PathfindParams,
+    ) -> Option<Vec<(u32, u32)>> {
+        let first = self.static_tiled_layers.first()?;
+        let width = first.width as u32;
+        let height = (first.static_colliders.len() / first.width) as u32;
+
+        let tile_at = |x: u32, y: u32| {
+            self.static_tiled_layers
+                .iter()
+                .filter_map(|layer| {
+                    layer
+                        .static_colliders
+                        .get((y as usize) * layer.width + x as usize)
+                })
+                .copied()
+                .find(|tile| *tile != Tile::Empty)
+                .unwrap_or(Tile::Empty)
+        };
+
+        find_path(
+            width,
+            height,
+            start,
+            goal,
+            |x, y| passable(tile_at(x, y)),
+            params,
+        )
+    }
+
     pub fn squished(&self, actor: Actor) -> bool {
         self.actors[actor.0].1.squished
     }
@@ -451,3 +497,21 @@ impl World {
         }
     }
 }
+
+#[test]
+fn rectangular_tiles_map_to_the_right_cell() {
+    // 3x2 layer of 16x32 tiles, solid only at column 2, row 1
+    let mut colliders = vec![Tile::Empty; 6];
+    colliders[5] = Tile::Solid;
+
+    let mut world = World::new();
+    world.add_static_tiled_layer(colliders, 16., 32., 3, 1);
+
+    assert!(world.solid_at(vec2(40., 40.)));
+    assert!(!world.solid_at(vec2(40., 20.)));
+    // with the tile sizes swapped this would land in the solid cell
+    assert!(!world.solid_at(vec2(70., 20.)));
+
+    assert_eq!(world.collide_tag(1, vec2(34., 34.), 4, 4), Tile::Solid);
+    assert_eq!(world.collide_tag(1, vec2(20., 34.), 4, 4), Tile::Empty);
+}
@@ -0,0 +1,189 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// Estimate of the remaining distance used by [find_path].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Heuristic {
+    /// Sum of the axis distances, exact for 4-directional movement.
+    Manhattan,
+    /// Exact for 8-directional movement with diagonal steps costing sqrt(2).
+    Octile,
+    Euclidean,
+    /// No estimate at all, turns A* into Dijkstra.
+    Zero,
+}
+
+impl Heuristic {
+    fn estimate(self, (x0, y0): (u32, u32), (x1, y1): (u32, u32)) -> f32 {
+        let dx = (x0 as f32 - x1 as f32).abs();
+        let dy = (y0 as f32 - y1 as f32).abs();
+        match self {
+            Heuristic::Manhattan => dx + dy,
+            Heuristic::Octile => dx.max(dy) + (std::f32::consts::SQRT_2 - 1.0) * dx.min(dy),
+            Heuristic::Euclidean => (dx * dx + dy * dy).sqrt(),
+            Heuristic::Zero => 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PathfindParams {
+    /// Allow diagonal steps. Corners are never cut: a diagonal step needs
+    /// both of the tiles next to it to be passable.
+    pub diagonal: bool,
+    pub heuristic: Heuristic,
+}
+
+impl Default for PathfindParams {
+    fn default() -> PathfindParams {
+        PathfindParams {
+            diagonal: false,
+            heuristic: Heuristic::Manhattan,
+        }
+    }
+}
+
+struct Node {
+    estimate: f32,
+    ix: usize,
+}
+
+impl PartialEq for Node {
+    fn eq(&self, other: &Node) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Node {}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Node) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Node {
+    // reversed, BinaryHeap is a max-heap and the cheapest node should be on top
+    fn cmp(&self, other: &Node) -> Ordering {
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+/// A* over a `width` x `height` tile grid.
+///
+/// Returns the tiles from `start` to `goal`, both included, or `None` when
+/// the goal is not reachable. `passable(x, y)` tells if a tile can be walked through.
+pub fn find_path(
+    width: u32,
+    height: u32,
+    start: (u32, u32),
+    goal: (u32, u32),
+    passable: impl Fn(u32, u32) -> bool,
+    params: PathfindParams,
+) -> Option<Vec<(u32, u32)>> {
+    let in_grid = |(x, y): (u32, u32)| x < width && y < height;
+    if !in_grid(start) || !in_grid(goal) || !passable(goal.0, goal.1) {
+        return None;
+    }
+
+    let index = |(x, y): (u32, u32)| (y * width + x) as usize;
+    let tile = |ix: usize| (ix as u32 % width, ix as u32 / width);
+
+    let mut cost = vec![f32::INFINITY; (width * height) as usize];
+    let mut came_from = vec![usize::MAX; (width * height) as usize];
+    let mut open = BinaryHeap::new();
+
+    cost[index(start)] = 0.0;
+    open.push(Node {
+        estimate: params.heuristic.estimate(start, goal),
+        ix: index(start),
+    });
+
+    while let Some(Node { estimate, ix }) = open.pop() {
+        let current = tile(ix);
+        if current == goal {
+            let mut path = vec![current];
+            let mut ix = ix;
+            while came_from[ix] != usize::MAX {
+                ix = came_from[ix];
+                path.push(tile(ix));
+            }
+            path.reverse();
+            return Some(path);
+        }
+        // an outdated entry, the tile was reached cheaper since
+        if estimate > cost[ix] + params.heuristic.estimate(current, goal) {
+            continue;
+        }
+
+        let (x, y) = (current.0 as i64, current.1 as i64);
+        let walkable = |x: i64, y: i64| {
+            x >= 0 && y >= 0 && in_grid((x as u32, y as u32)) && passable(x as u32, y as u32)
+        };
+        for (dx, dy) in [
+            (1, 0),
+            (-1, 0),
+            (0, 1),
+            (0, -1),
+            (1, 1),
+            (1, -1),
+            (-1, 1),
+            (-1, -1),
+        ] {
+            let diagonal = dx != 0 && dy != 0;
+            if diagonal && (!params.diagonal || !walkable(x + dx, y) || !walkable(x, y + dy)) {
+                continue;
+            }
+            if !walkable(x + dx, y + dy) {
+                continue;
+            }
+
+            let next = ((x + dx) as u32, (y + dy) as u32);
+            let step = if diagonal {
+                std::f32::consts::SQRT_2
+            } else {
+                1.0
+            };
+            let next_cost = cost[ix] + step;
+            if next_cost < cost[index(next)] {
+                cost[index(next)] = next_cost;
+                came_from[index(next)] = ix;
+                open.push(Node {
+                    estimate: next_cost + params.heuristic.estimate(next, goal),
+                    ix: index(next),
+                });
+            }
+        }
+    }
+
+    None
+}
+
+#[test]
+fn path_goes_around_the_wall() {
+    // 5x5 grid with a wall in the middle column, open only at the bottom
+    let wall = |x: u32, y: u32| !(x == 2 && y < 4);
+
+    let path = find_path(5, 5, (0, 0), (4, 0), wall, PathfindParams::default()).unwrap();
+    assert_eq!(path.first(), Some(&(0, 0)));
+    assert_eq!(path.last(), Some(&(4, 0)));
+    assert!(path.contains(&(2, 4)));
+    // 4 down, 4 right, 4 up
+    assert_eq!(path.len(), 13);
+
+    let diagonal = PathfindParams {
+        diagonal: true,
+        heuristic: Heuristic::Octile,
+    };
+    let path = find_path(5, 5, (0, 0), (4, 0), wall, diagonal).unwrap();
+    assert!(path.len() < 13);
+    for step in path.windows(2) {
+        assert!(step[0].0.abs_diff(step[1].0) <= 1 && step[0].1.abs_diff(step[1].1) <= 1);
+    }
+
+    let closed = |x: u32, _: u32| x != 2;
+    assert_eq!(
+        find_path(5, 5, (0, 0), (4, 0), closed, PathfindParams::default()),
+        None
+    );
+}
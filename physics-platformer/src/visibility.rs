@@ -0,0 +1,155 @@
+use macroquad::math::{vec2, Vec2};
+
+use std::f32::consts::TAU;
+
+// rays around the range circle, so open areas are still round-ish
+const CIRCLE_RAYS: usize = 64;
+// angle offset of the rays passing just by a tile corner
+const CORNER_EPSILON: f32 = 0.0001;
+
+/// Polygon of everything visible from `origin` on a tile grid, for fog of war or 2D lights.
+///
+/// `solid(x, y)` tells if the tile at the given tile coordinates blocks the view,
+/// tiles are `tile_size` big and the grid starts at (0, 0) in world space.
+/// The view reaches at most `max_distance` away.
+///
+/// Points are in world space, sorted by angle around `origin`, so a fan from
+/// `origin` through the points covers the visible area:
+///
+/// ```ignore
+/// let polygon = visibility_polygon(player, 300., vec2(16., 16.), |x, y| {
+///     x < 0 || y < 0 || x >= width || y >= height || tiles[(y * width + x) as usize] == Tile::Solid
+/// });
+/// for (i, a) in polygon.iter().enumerate() {
+///     let b = polygon[(i + 1) % polygon.len()];
+///     draw_triangle(player, *a, b, LIGHT);
+/// }
+/// ```
+///
+/// Returns an empty polygon when `origin` is inside a solid tile.
+pub fn visibility_polygon(
+    origin: Vec2,
+    max_distance: f32,
+    tile_size: Vec2,
+    solid: impl Fn(i32, i32) -> bool,
+) -> Vec<Vec2> {
+    let origin_tile = origin / tile_size;
+    if solid(origin_tile.x.floor() as i32, origin_tile.y.floor() as i32) {
+        return vec![];
+    }
+
+    let mut angles = (0..CIRCLE_RAYS)
+        .map(|i| i as f32 / CIRCLE_RAYS as f32 * TAU)
+        .collect::<Vec<_>>();
+
+    // a ray just by each side of every solid tile corner in range,
+    // this is where the silhouette of the walls changes
+    let range = (vec2(max_distance, max_distance) / tile_size).ceil();
+    let (min, max) = ((origin_tile - range).floor(), (origin_tile + range).ceil());
+    for y in min.y as i32..=max.y as i32 {
+        for x in min.x as i32..=max.x as i32 {
+            if !solid(x, y) {
+                continue;
+            }
+            for corner in [(x, y), (x + 1, y), (x + 1, y + 1), (x, y + 1)] {
+                let corner = vec2(corner.0 as f32, corner.1 as f32) * tile_size;
+                let angle = (corner - origin).y.atan2((corner - origin).x);
+                angles.extend([angle - CORNER_EPSILON, angle, angle + CORNER_EPSILON]);
+            }
+        }
+    }
+
+    let mut points = angles
+        .into_iter()
+        .map(|angle| {
+            // atan2 gives (-PI, PI], sort everything in the same [0, TAU) range
+            let angle = angle.rem_euclid(TAU);
+            let dir = vec2(angle.cos(), angle.sin());
+            let distance = cast_ray(origin_tile, dir / tile_size, max_distance, &solid);
+            (angle, origin + dir * distance)
+        })
+        .collect::<Vec<_>>();
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+    points.dedup_by(|a, b| a.1.distance_squared(b.1) < 0.0001);
+
+    points.into_iter().map(|(_, point)| point).collect()
+}
+
+/// Walk the grid from `origin` along `dir`, both in tiles, and return the world
+/// distance to the first solid tile or `max_distance`.
+fn cast_ray(origin: Vec2, dir: Vec2, max_distance: f32, solid: impl Fn(i32, i32) -> bool) -> f32 {
+    let mut cell = (origin.x.floor() as i32, origin.y.floor() as i32);
+
+    let axis = |origin: f32, dir: f32, cell: i32| {
+        if dir > 0.0 {
+            (1, (cell as f32 + 1.0 - origin) / dir, 1.0 / dir)
+        } else if dir < 0.0 {
+            (-1, (origin - cell as f32) / -dir, -1.0 / dir)
+        } else {
+            (0, f32::INFINITY, f32::INFINITY)
+        }
+    };
+    let (step_x, mut next_x, delta_x) = axis(origin.x, dir.x, cell.0);
+    let (step_y, mut next_y, delta_y) = axis(origin.y, dir.y, cell.1);
+
+    loop {
+        let distance = next_x.min(next_y);
+        if distance >= max_distance {
+            return max_distance;
+        }
+
+        if next_x < next_y {
+            cell.0 += step_x;
+            next_x += delta_x;
+        } else {
+            cell.1 += step_y;
+            next_y += delta_y;
+        }
+
+        if solid(cell.0, cell.1) {
+            return distance;
+        }
+    }
+}
+
+#[test]
+fn wall_blocks_the_view() {
+    // a wall along x = 3..4 tiles, 10 pixel tiles
+    let wall = |x: i32, _: i32| x == 3;
+    let polygon = visibility_polygon(vec2(5., 5.), 100., vec2(10., 10.), wall);
+
+    assert!(!polygon.is_empty());
+    assert!(polygon.iter().all(|point| point.x <= 30.001));
+    // straight to the right the view stops on the wall
+    assert!(polygon
+        .iter()
+        .any(|point| (point.x - 30.).abs() < 0.001 && (point.y - 5.).abs() < 0.001));
+    // and to the left it reaches the range
+    assert!(polygon
+        .iter()
+        .any(|point| (point.x + 95.).abs() < 0.001 && (point.y - 5.).abs() < 0.001));
+
+    assert!(visibility_polygon(vec2(35., 5.), 100., vec2(10., 10.), wall).is_empty());
+}
+
+#[test]
+fn points_are_sorted_around_a_wall_above() {
+    // a wall along y = -2 tiles, crossing the angle wrap around straight left
+    let wall = |_: i32, y: i32| y == -2;
+    let origin = vec2(5., 5.);
+    let polygon = visibility_polygon(origin, 100., vec2(10., 10.), wall);
+
+    let angles = polygon
+        .iter()
+        .map(|point| {
+            (*point - origin)
+                .y
+                .atan2((*point - origin).x)
+                .rem_euclid(TAU)
+        })
+        .collect::<Vec<_>>();
+    assert!(angles.len() > CIRCLE_RAYS);
+    assert!(angles.windows(2).all(|pair| pair[0] <= pair[1] + 0.0001));
+    // the view upwards stops on the wall
+    assert!(polygon.iter().all(|point| point.y >= -10.001));
+}
@@ -266,9 +266,13 @@ pub fn profiler(params: ProfilerParams) {
                     for telemetry::DrawCallTelemetry {
                         indices_count,
                         texture,
+                        debug_group,
                     } in &drawcalls
                     {
-                        ui.label(None, &format!("{}", *indices_count));
+                        match debug_group {
+                            Some(group) => ui.label(None, &format!("{}: {}", group, indices_count)),
+                            None => ui.label(None, &format!("{}", indices_count)),
+                        }
                         ui.same_line(0.0);
                         ui.texture(Texture2D::from_miniquad_texture(*texture), 100., 100.0);
                         ui.same_line(0.0);
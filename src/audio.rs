@@ -2,7 +2,7 @@
 
 #![allow(dead_code)]
 
-use crate::{file::load_file, get_context, Error};
+use crate::{file::load_file, get_context, math::Vec2, Error};
 use std::sync::Arc;
 
 #[cfg(feature = "audio")]
@@ -64,12 +64,14 @@ pub struct PlaySoundParams {
 
 pub struct AudioContext {
     native_ctx: QuadSndContext,
+    listener_position: Vec2,
 }
 
 impl AudioContext {
     pub fn new() -> AudioContext {
         AudioContext {
             native_ctx: QuadSndContext::new(),
+            listener_position: Vec2::ZERO,
         }
     }
 
@@ -156,3 +158,78 @@ pub fn set_sound_volume(sound: &Sound, volume: f32) {
     let ctx = &mut get_context().audio_context;
     sound.0 .0.set_volume(&mut ctx.native_ctx, volume);
 }
+
+/// How the volume of `play_sound_at` sounds decreases with the distance from the listener.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SoundFalloff {
+    /// Full volume at the listener, fading linearly down to silence at `max_distance`.
+    Linear { max_distance: f32 },
+    /// Full volume up to `ref_distance`, then `ref_distance / distance`,
+    /// silent beyond `max_distance`. Sounds more natural than linear.
+    Inverse {
+        ref_distance: f32,
+        max_distance: f32,
+    },
+}
+
+impl SoundFalloff {
+    fn volume(&self, distance: f32) -> f32 {
+        match *self {
+            SoundFalloff::Linear { max_distance } => (1. - distance / max_distance).clamp(0., 1.),
+            SoundFalloff::Inverse {
+                ref_distance,
+                max_distance,
+            } => {
+                if distance > max_distance {
+                    0.
+                } else {
+                    (ref_distance / distance.max(ref_distance)).clamp(0., 1.)
+                }
+            }
+        }
+    }
+}
+
+/// Position of the listener for `play_sound_at`, usually the player or the camera target.
+pub fn set_listener_position(position: Vec2) {
+    get_context().audio_context.listener_position = position;
+}
+
+/// Play a sound emitted at `position`, in the same space as `set_listener_position`.
+///
+/// The volume is `params.volume` attenuated by the distance to the listener,
+/// sounds beyond the falloff's max distance are not played at all.
+/// Only the volume is affected: the audio backend has no stereo panning.
+pub fn play_sound_at(
+    sound: &Sound,
+    position: Vec2,
+    params: PlaySoundParams,
+    falloff: SoundFalloff,
+) {
+    let distance = get_context()
+        .audio_context
+        .listener_position
+        .distance(position);
+    let volume = params.volume * falloff.volume(distance);
+    if volume <= 0. {
+        return;
+    }
+
+    play_sound(sound, PlaySoundParams { volume, ..params });
+}
+
+#[test]
+fn sound_falloff() {
+    let linear = SoundFalloff::Linear { max_distance: 100. };
+    assert_eq!(linear.volume(0.), 1.);
+    assert_eq!(linear.volume(25.), 0.75);
+    assert_eq!(linear.volume(200.), 0.);
+
+    let inverse = SoundFalloff::Inverse {
+        ref_distance: 10.,
+        max_distance: 100.,
+    };
+    assert_eq!(inverse.volume(5.), 1.);
+    assert_eq!(inverse.volume(40.), 0.25);
+    assert_eq!(inverse.volume(101.), 0.);
+}
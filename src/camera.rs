@@ -5,7 +5,7 @@ use crate::{
     math::Rect,
     prelude::RenderPass,
     texture::RenderTarget,
-    window::{screen_height, screen_width},
+    window::{screen_dpi_scale, screen_height, screen_size_physical, screen_width},
 };
 use glam::{vec2, vec3, Mat4, Vec2, Vec3};
 
@@ -57,6 +57,116 @@ impl Camera2D {
             viewport: None,
         }
     }
+
+    /// Camera for crisp pixel art: one world unit is exactly `zoom` screen pixels,
+    /// with (0, 0) at the top left corner of the screen.
+    ///
+    /// Texel edges land exactly on pixel edges, so sprites drawn at integer world
+    /// coordinates are neither blurred nor shifted by half a pixel.
+    ///
+    /// The camera depends on the screen size, so it should be re-created when the window
+    /// is resized, the easiest way is just to build it each frame:
+    /// ```ignore
+    /// set_camera(&Camera2D::pixel_perfect(screen_width(), screen_height(), 3));
+    /// ```
+    pub fn pixel_perfect(screen_width: f32, screen_height: f32, zoom: u32) -> Camera2D {
+        let zoom = zoom.max(1) as f32;
+
+        Camera2D {
+            target: vec2(screen_width / zoom / 2., screen_height / zoom / 2.),
+            zoom: vec2(2. * zoom / screen_width, 2. * zoom / screen_height),
+            ..Default::default()
+        }
+    }
+
+    /// Camera showing `0..width` x `0..height` (y going down) with a fixed aspect ratio.
+    ///
+    /// The picture is as big as fits the window and centered, the rest of the
+    /// window is left as bars on the sides or on the top and bottom, see [letterbox_viewport].
+    /// Re-create it each frame to follow window resizes:
+    /// ```ignore
+    /// clear_background(BLACK);
+    /// let camera = Camera2D::letterboxed(320., 180.);
+    /// set_camera(&camera);
+    /// draw_rectangle(0., 0., 320., 180., SKYBLUE);
+    /// // clicks in game coordinates
+    /// let click = camera.screen_to_world(mouse_position().into());
+    /// ```
+    pub fn letterboxed(width: f32, height: f32) -> Camera2D {
+        Camera2D {
+            target: vec2(width / 2., height / 2.),
+            zoom: vec2(2. / width, 2. / height),
+            viewport: Some(letterbox_viewport(width / height)),
+            ..Default::default()
+        }
+    }
+}
+
+/// The biggest centered viewport with the given width / height ratio that fits the window.
+///
+/// Same units as `Camera2D::viewport`: physical pixels, counted from the bottom left corner.
+pub fn letterbox_viewport(target_aspect: f32) -> (i32, i32, i32, i32) {
+    let (width, height) = screen_size_physical();
+    letterbox(width as f32, height as f32, target_aspect)
+}
+
+fn letterbox(width: f32, height: f32, target_aspect: f32) -> (i32, i32, i32, i32) {
+    let (w, h) = if width / height > target_aspect {
+        (height * target_aspect, height)
+    } else {
+        (width, width / target_aspect)
+    };
+    let (x, y) = ((width - w) / 2., (height - h) / 2.);
+
+    (x as i32, y as i32, w as i32, h as i32)
+}
+
+#[test]
+fn letterbox_centers_the_viewport() {
+    // wide window, bars on the sides
+    assert_eq!(letterbox(1000., 450., 16. / 9.), (100, 0, 800, 450));
+    // tall window, bars on the top and bottom
+    assert_eq!(letterbox(800., 800., 16. / 9.), (0, 175, 800, 450));
+
+    // 1000x450 logical window at 2x dpi
+    let viewport = letterbox(2000., 900., 16. / 9.);
+    let dims = logical_viewport(viewport, 450., 2.);
+    assert_eq!(dims, Rect::new(100., 0., 800., 450.));
+
+    let camera = Camera2D {
+        zoom: vec2(2. / 160., 2. / 90.),
+        target: vec2(80., 45.),
+        viewport: Some(viewport),
+        ..Default::default()
+    };
+    let to_screen = |point: Vec2| {
+        let ndc = camera.matrix().transform_point3(vec3(point.x, point.y, 0.));
+        ndc_to_screen(vec2(ndc.x, ndc.y), dims)
+    };
+    assert!(to_screen(vec2(80., 45.)).abs_diff_eq(vec2(500., 225.), 1e-3));
+    assert!(to_screen(vec2(0., 0.)).abs_diff_eq(vec2(100., 0.), 1e-3));
+
+    for point in [vec2(0., 0.), vec2(12., 80.), vec2(160., 90.)] {
+        let ndc = screen_to_ndc(to_screen(point), dims);
+        let world = camera
+            .matrix()
+            .inverse()
+            .transform_point3(vec3(ndc.x, ndc.y, 0.));
+        assert!(vec2(world.x, world.y).abs_diff_eq(point, 1e-3));
+    }
+}
+
+#[test]
+fn pixel_perfect_camera_maps_texels_to_pixels() {
+    let camera = Camera2D::pixel_perfect(801., 600., 3);
+    let to_pixels = |point: Vec2| {
+        let ndc = camera.matrix().transform_point3(vec3(point.x, point.y, 0.));
+        vec2((ndc.x + 1.) / 2. * 801., (1. - ndc.y) / 2. * 600.)
+    };
+
+    assert!(to_pixels(vec2(0., 0.)).abs_diff_eq(vec2(0., 0.), 1e-3));
+    assert!(to_pixels(vec2(1., 1.)).abs_diff_eq(vec2(3., 3.), 1e-3));
+    assert!(to_pixels(vec2(100., 50.)).abs_diff_eq(vec2(300., 150.), 1e-3));
 }
 
 impl Default for Camera2D {
@@ -120,14 +230,15 @@ impl Camera for Camera2D {
 impl Camera2D {
     /// Returns the screen space position for a 2d camera world space position.
     ///
-    /// Screen position in window space - from (0, 0) to (screen_width, screen_height()).
+    /// Screen position in window space - from (0, 0) to (screen_width, screen_height()),
+    /// inside the camera viewport when it has one.
     pub fn world_to_screen(&self, point: Vec2) -> Vec2 {
         let mat = self.matrix();
         let transform = mat.transform_point3(vec3(point.x, point.y, 0.));
 
-        vec2(
-            (transform.x / 2. + 0.5) * screen_width(),
-            (0.5 - transform.y / 2.) * screen_height(),
+        ndc_to_screen(
+            vec2(transform.x, transform.y),
+            viewport_rect(self.viewport()),
         )
     }
 
@@ -135,25 +246,7 @@ impl Camera2D {
     ///
     /// Point is a screen space position, often mouse x and y.
     pub fn screen_to_world(&self, point: Vec2) -> Vec2 {
-        let dims = self
-            .viewport()
-            .map(|(vx, vy, vw, vh)| Rect {
-                x: vx as f32,
-                y: screen_height() - (vy + vh) as f32,
-                w: vw as f32,
-                h: vh as f32,
-            })
-            .unwrap_or(Rect {
-                x: 0.0,
-                y: 0.0,
-                w: screen_width(),
-                h: screen_height(),
-            });
-
-        let point = vec2(
-            (point.x - dims.x) / dims.w * 2. - 1.,
-            1. - (point.y - dims.y) / dims.h * 2.,
-        );
+        let point = screen_to_ndc(point, viewport_rect(self.viewport()));
         let inv_mat = self.matrix().inverse();
         let transform = inv_mat.transform_point3(vec3(point.x, point.y, 0.));
 
@@ -161,6 +254,43 @@ impl Camera2D {
     }
 }
 
+/// The area of the screen the camera draws to, in logical pixels from the top left corner.
+fn viewport_rect(viewport: Option<(i32, i32, i32, i32)>) -> Rect {
+    viewport
+        .map(|viewport| logical_viewport(viewport, screen_height(), screen_dpi_scale()))
+        .unwrap_or(Rect {
+            x: 0.0,
+            y: 0.0,
+            w: screen_width(),
+            h: screen_height(),
+        })
+}
+
+fn logical_viewport((vx, vy, vw, vh): (i32, i32, i32, i32), screen_height: f32, dpi: f32) -> Rect {
+    // viewport is in physical pixels from the bottom left, screen positions are logical ones
+    Rect {
+        x: vx as f32 / dpi,
+        y: screen_height - (vy + vh) as f32 / dpi,
+        w: vw as f32 / dpi,
+        h: vh as f32 / dpi,
+    }
+}
+
+/// Screen space position to normalized device coordinates of the given viewport.
+fn screen_to_ndc(point: Vec2, dims: Rect) -> Vec2 {
+    vec2(
+        (point.x - dims.x) / dims.w * 2. - 1.,
+        1. - (point.y - dims.y) / dims.h * 2.,
+    )
+}
+
+fn ndc_to_screen(point: Vec2, dims: Rect) -> Vec2 {
+    vec2(
+        dims.x + (point.x + 1.) / 2. * dims.w,
+        dims.y + (1. - point.y) / 2. * dims.h,
+    )
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum Projection {
     Perspective,
@@ -218,6 +348,42 @@ impl Default for Camera3D {
 impl Camera3D {
     const Z_NEAR: f32 = 0.01;
     const Z_FAR: f32 = 10000.0;
+
+    /// Returns a world space ray, (origin, normalized direction), going from the camera's near plane
+    /// through the screen space position.
+    ///
+    /// Point is a screen space position, often mouse x and y. Useful for picking 3D objects with the mouse.
+    pub fn screen_to_world_ray(&self, point: Vec2) -> (Vec3, Vec3) {
+        ndc_to_world_ray(
+            self.matrix(),
+            screen_to_ndc(point, viewport_rect(self.viewport())),
+        )
+    }
+}
+
+fn ndc_to_world_ray(matrix: Mat4, point: Vec2) -> (Vec3, Vec3) {
+    let inv_mat = matrix.inverse();
+    let near = inv_mat.project_point3(vec3(point.x, point.y, -1.));
+    let far = inv_mat.project_point3(vec3(point.x, point.y, 1.));
+
+    (near, (far - near).normalize())
+}
+
+#[test]
+fn screen_center_ray_points_at_target() {
+    let (position, target) = (vec3(0., -10., 2.), vec3(0., 0., 2.));
+    let view = Mat4::look_at_rh(position, target, vec3(0., 0., 1.));
+    let (near, far) = (Camera3D::Z_NEAR, Camera3D::Z_FAR);
+
+    for projection in [
+        Mat4::perspective_rh_gl(45f32.to_radians(), 1.5, near, far),
+        Mat4::orthographic_rh_gl(-15., 15., -10., 10., near, far),
+    ] {
+        let (origin, direction) = ndc_to_world_ray(projection * view, vec2(0., 0.));
+
+        assert!(origin.abs_diff_eq(vec3(0., -10. + Camera3D::Z_NEAR, 2.), 1e-3));
+        assert!(direction.abs_diff_eq(vec3(0., 1., 0.), 1e-4));
+    }
 }
 
 impl Camera for Camera3D {
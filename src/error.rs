@@ -1,12 +1,18 @@
 #[derive(Debug)]
 pub enum Error {
     FontError(&'static str),
+    ModelError(&'static str),
     FileError {
         kind: miniquad::fs::Error,
         path: String,
     },
     ShaderError(miniquad::ShaderError),
     ImageError(image::ImageError),
+    TextureAtlasTooBig {
+        width: u32,
+        height: u32,
+        max_size: u32,
+    },
     UnknownError(&'static str),
 }
 
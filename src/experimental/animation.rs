@@ -57,7 +57,9 @@
 //! }
 
 use crate::{
+    color::Color,
     math::{vec2, Rect, Vec2},
+    texture::{draw_texture_ex, DrawTextureParams, Texture2D},
     time::get_frame_time,
 };
 
@@ -90,6 +92,8 @@ pub struct AnimatedSprite {
     frame: u32,
     /// Controls if frame should be updated on [update][Self::update]
     pub playing: bool,
+    /// If false, animations stop on their last frame instead of starting over
+    pub looping: bool,
 }
 
 impl AnimatedSprite {
@@ -107,6 +111,7 @@ impl AnimatedSprite {
             time: 0.0,
             frame: 0,
             playing,
+            looping: true,
         }
     }
 
@@ -120,6 +125,15 @@ impl AnimatedSprite {
         self.frame %= animation.frames;
     }
 
+    /// Choose animation to display by its name
+    ///
+    /// Does nothing if there is no animation with this name
+    pub fn set_animation_by_name(&mut self, name: &str) {
+        if let Some(animation) = self.animations.iter().position(|a| a.name == name) {
+            self.set_animation(animation);
+        }
+    }
+
     /// Currently chosen animation
     pub const fn current_animation(&self) -> usize {
         self.current_animation
@@ -142,7 +156,7 @@ impl AnimatedSprite {
     pub fn update(&mut self) {
         let animation = &self.animations[self.current_animation];
 
-        if self.playing {
+        if self.playing && (self.looping || !self.is_last_frame()) {
             self.time += get_frame_time();
             if self.time > 1. / animation.fps as f32 {
                 self.frame += 1;
@@ -166,4 +180,21 @@ impl AnimatedSprite {
             dest_size: vec2(self.tile_width, self.tile_height),
         }
     }
+
+    /// Draw current frame with its top-left corner at `[x, y]`
+    pub fn draw(&self, texture: &Texture2D, x: f32, y: f32, color: Color) {
+        let frame = self.frame();
+
+        draw_texture_ex(
+            texture,
+            x,
+            y,
+            color,
+            DrawTextureParams {
+                source: Some(frame.source_rect),
+                dest_size: Some(frame.dest_size),
+                ..Default::default()
+            },
+        );
+    }
 }
@@ -0,0 +1,78 @@
+use crate::math::{vec2, Vec2};
+
+/// Trauma-based screen shake.
+///
+/// Does not know anything about cameras: `update` returns an offset and a rotation
+/// that could be added to any camera's `offset`/`target` and `rotation`.
+///
+/// ```ignore
+/// let mut shake = CameraShake::new(vec2(8., 8.), 5.);
+/// // on explosion
+/// shake.trauma(0.5);
+/// // each frame
+/// let (offset, rotation) = shake.update(get_frame_time());
+/// set_camera(&Camera2D {
+///     target: player_pos + offset,
+///     rotation,
+///     ..camera
+/// });
+/// ```
+#[derive(Debug, Clone)]
+pub struct CameraShake {
+    /// Offset at full trauma, in camera units.
+    pub max_offset: Vec2,
+    /// Rotation at full trauma, in degrees, same as Camera2D::rotation.
+    pub max_rotation: f32,
+    /// How much trauma is removed each second.
+    pub decay: f32,
+
+    trauma: f32,
+}
+
+impl CameraShake {
+    pub fn new(max_offset: Vec2, max_rotation: f32) -> CameraShake {
+        CameraShake {
+            max_offset,
+            max_rotation,
+            decay: 1.0,
+            trauma: 0.0,
+        }
+    }
+
+    /// Add trauma, total trauma is clamped to [0, 1].
+    pub fn trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).clamp(0.0, 1.0);
+    }
+
+    /// Current trauma, 0.0 means no shake.
+    pub fn current_trauma(&self) -> f32 {
+        self.trauma
+    }
+
+    /// Decay the trauma and return (offset, rotation) for this frame.
+    pub fn update(&mut self, dt: f32) -> (Vec2, f32) {
+        // squared trauma feels better than linear: small hits barely shake,
+        // big ones shake a lot
+        let shake = self.trauma * self.trauma;
+
+        self.trauma = (self.trauma - self.decay * dt).max(0.0);
+
+        if shake == 0.0 {
+            return (vec2(0., 0.), 0.);
+        }
+
+        let offset = vec2(
+            self.max_offset.x * shake * quad_rand::gen_range(-1.0, 1.0),
+            self.max_offset.y * shake * quad_rand::gen_range(-1.0, 1.0),
+        );
+        let rotation = self.max_rotation * shake * quad_rand::gen_range(-1.0, 1.0);
+
+        (offset, rotation)
+    }
+}
+
+impl Default for CameraShake {
+    fn default() -> CameraShake {
+        CameraShake::new(vec2(10., 10.), 5.)
+    }
+}
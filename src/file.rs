@@ -2,30 +2,94 @@
 
 use crate::{exec, Error};
 
+use std::future::Future;
+
 /// Load file from the path and block until its loaded
 /// Will use filesystem on PC and do http request on web
 pub async fn load_file(path: &str) -> Result<Vec<u8>, Error> {
-    fn load_file_inner(path: &str) -> exec::FileLoadingFuture {
-        use std::sync::{Arc, Mutex};
+    load_file_inner(&resolve_path(path)).await
+}
 
-        let contents = Arc::new(Mutex::new(None));
-        let path = path.to_owned();
+fn load_file_inner(path: &str) -> exec::FileLoadingFuture {
+    use std::sync::{Arc, Mutex};
+
+    let contents = Arc::new(Mutex::new(None));
+    let path = path.to_owned();
 
-        {
-            let contents = contents.clone();
-            let err_path = path.clone();
+    {
+        let contents = contents.clone();
+        let err_path = path.clone();
 
-            miniquad::fs::load_file(&path, move |bytes| {
-                *contents.lock().unwrap() = Some(bytes.map_err(|kind| Error::FileError {
-                    kind,
-                    path: err_path.clone(),
-                }));
+        miniquad::fs::load_file(&path, move |bytes| {
+            *contents.lock().unwrap() = Some(bytes.map_err(|kind| Error::FileError {
+                kind,
+                path: err_path.clone(),
+            }));
+        });
+    }
+
+    exec::FileLoadingFuture { contents }
+}
+
+/// Same as [load_file], but on desktop the file is read on a worker thread.
+///
+/// [load_file] reads files on the main thread, so a big file stalls the frame
+/// even when loaded from a coroutine. This keeps a loading screen animating:
+/// ```ignore
+/// let level = start_coroutine(load_file_threaded("level.bin"));
+/// while !level.is_done() {
+///     draw_loading_screen();
+///     next_frame().await;
+/// }
+/// let level = level.retrieve().unwrap()?;
+/// ```
+/// On web files are already loaded asynchronously, and on mobile they have to go
+/// through the platform asset APIs, so there it behaves like [load_file].
+pub fn load_file_threaded(
+    path: &str,
+) -> impl Future<Output = Result<Vec<u8>, Error>> + Send + 'static {
+    let path = resolve_path(path);
+
+    #[cfg(any(target_arch = "wasm32", target_os = "android", target_os = "ios"))]
+    return load_file_inner(&path);
+
+    #[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+    {
+        use std::sync::{Arc, Mutex};
+
+        let contents = Arc::new(Mutex::new(None));
+        let result = contents.clone();
+        std::thread::spawn(move || {
+            let bytes = std::fs::read(&path).map_err(|err| Error::FileError {
+                kind: miniquad::fs::Error::IOError(err),
+                path,
             });
-        }
+            *result.lock().unwrap() = Some(bytes);
+        });
 
         exec::FileLoadingFuture { contents }
     }
+}
 
+/// Load file from the path, blocking the current thread until its loaded.
+///
+/// Not available on web, where files are loaded with an http request that can't be waited on.
+/// Prefer `load_file` in the game code, this one is handy for setup code
+/// and tools that are not async.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_file_blocking(path: &str) -> Result<Vec<u8>, Error> {
+    // on all non-web platforms miniquad calls the callback before returning
+    let contents = load_file_inner(&resolve_path(path))
+        .contents
+        .lock()
+        .unwrap()
+        .take();
+    contents.unwrap_or(Err(Error::UnknownError(
+        "File was not loaded synchronously",
+    )))
+}
+
+fn resolve_path(path: &str) -> String {
     #[cfg(target_os = "ios")]
     let _ = std::env::set_current_dir(std::env::current_exe().unwrap().parent().unwrap());
 
@@ -36,7 +100,10 @@ pub async fn load_file(path: &str) -> Result<Vec<u8>, Error> {
         path.to_string()
     };
 
-    load_file_inner(&path).await
+    #[cfg(target_os = "android")]
+    let path = path.to_string();
+
+    path
 }
 
 /// Load string from the path and block until its loaded.
@@ -6,7 +6,7 @@ use crate::prelude::screen_height;
 use crate::prelude::screen_width;
 use crate::Vec2;
 use crate::{get_context, DroppedFile};
-pub use miniquad::{KeyCode, MouseButton};
+pub use miniquad::{KeyCode, KeyMods, MouseButton};
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum TouchPhase {
@@ -35,7 +35,34 @@ pub struct Touch {
     pub position: Vec2,
 }
 
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct ClickState {
+    count: u32,
+    time: f64,
+    position: Vec2,
+}
+
+impl ClickState {
+    /// How far, in pixels, the cursor may travel between clicks of a double click.
+    const MAX_DISTANCE: f32 = 4.0;
+
+    pub(crate) fn click(&mut self, position: Vec2, time: f64, interval: f64) {
+        if self.count != 0
+            && time - self.time <= interval
+            && position.distance(self.position) <= Self::MAX_DISTANCE
+        {
+            self.count += 1;
+        } else {
+            self.count = 1;
+        }
+        self.time = time;
+        self.position = position;
+    }
+}
+
 /// Constrain mouse to window
+///
+/// The grab is re-applied every frame, as some platforms release it on focus loss.
 pub fn set_cursor_grab(grab: bool) {
     let context = get_context();
     context.cursor_grabbed = grab;
@@ -75,6 +102,18 @@ pub fn mouse_delta_position() -> Vec2 {
     last_position - current_position
 }
 
+/// Returns the relative mouse motion accumulated during the last frame, straight from the
+/// OS raw input events.
+///
+/// Unlike `mouse_delta_position`, it is not bound by the window borders and keeps
+/// reporting motion while the cursor is grabbed with `set_cursor_grab(true)`,
+/// which makes it the right thing for first person cameras.
+/// The units are whatever the OS reports, usually pixels/mouse counts, without
+/// any acceleration applied.
+pub fn mouse_raw_delta() -> Vec2 {
+    get_context().mouse_raw_delta
+}
+
 /// This is set to true by default, meaning touches will raise mouse events in addition to raising touch events.
 /// If set to false, touches won't affect mouse events.
 pub fn is_simulating_mouse_with_touch() -> bool {
@@ -132,6 +171,40 @@ pub fn is_key_released(key_code: KeyCode) -> bool {
     context.keys_released.contains(&key_code)
 }
 
+/// Detect if all the given keys are being pressed at the same time
+pub fn is_key_chord_down(keys: &[KeyCode]) -> bool {
+    let context = get_context();
+
+    keys.iter().all(|key| context.keys_down.contains(key))
+}
+
+/// Return currently held modifier keys, left and right variants are not distinguished.
+pub fn get_key_mods() -> KeyMods {
+    let context = get_context();
+    let down =
+        |left, right| context.keys_down.contains(&left) || context.keys_down.contains(&right);
+
+    KeyMods {
+        shift: down(KeyCode::LeftShift, KeyCode::RightShift),
+        ctrl: down(KeyCode::LeftControl, KeyCode::RightControl),
+        alt: down(KeyCode::LeftAlt, KeyCode::RightAlt),
+        logo: down(KeyCode::LeftSuper, KeyCode::RightSuper),
+    }
+}
+
+/// Detect if `key` has been pressed this frame while exactly `modifiers` were held.
+///
+/// Modifiers should match exactly, so Ctrl+Shift+S will not trigger a Ctrl+S shortcut.
+/// ```ignore
+/// let ctrl = KeyMods { ctrl: true, ..Default::default() };
+/// if is_key_combo_pressed(ctrl, KeyCode::S) {
+///     save();
+/// }
+/// ```
+pub fn is_key_combo_pressed(modifiers: KeyMods, key: KeyCode) -> bool {
+    is_key_pressed(key) && get_key_mods() == modifiers
+}
+
 /// Return the last pressed char.
 /// Each "get_char_pressed" call will consume a character from the input queue.
 pub fn get_char_pressed() -> Option<char> {
@@ -140,6 +213,17 @@ pub fn get_char_pressed() -> Option<char> {
     context.chars_pressed_queue.pop()
 }
 
+/// Return all the characters typed during the last frame, in the order they were typed.
+///
+/// Unlike `get_char_pressed`, this does not consume anything and is reset every frame.
+/// Characters come from the OS already composed, so IME input and non-ASCII characters
+/// arrive as complete `char`s.
+pub fn get_typed_chars() -> Vec<char> {
+    let context = get_context();
+
+    context.chars_typed.clone()
+}
+
 pub(crate) fn get_char_pressed_ui() -> Option<char> {
     let context = get_context();
 
@@ -196,15 +280,119 @@ pub fn is_mouse_button_released(btn: MouseButton) -> bool {
     context.mouse_released.contains(&btn)
 }
 
+/// Returns how many rapid successive clicks ended with the button press of this frame:
+/// 1 for a single click, 2 for a double click, 3 for a triple click etc.
+/// Returns 0 if the button was not pressed this frame.
+///
+/// Clicks count as successive when they are no more than `set_double_click_interval`
+/// apart (0.3s by default) and the cursor stays within a few pixels.
+pub fn mouse_click_count(btn: MouseButton) -> u32 {
+    let context = get_context();
+
+    if !context.mouse_pressed.contains(&btn) {
+        return 0;
+    }
+    context
+        .mouse_clicks
+        .get(&btn)
+        .map_or(0, |click| click.count)
+}
+
+/// Detect if the button has been double clicked this frame
+pub fn is_mouse_double_clicked(btn: MouseButton) -> bool {
+    mouse_click_count(btn) == 2
+}
+
+/// How much two fingers pinched since the last frame, as a ratio of the distance
+/// between them: above 1 when spreading apart, below 1 when pinching together.
+///
+/// Returns 1 unless exactly two fingers are touching the screen, so multiplying
+/// a camera zoom by it is always safe.
+pub fn pinch_zoom_delta() -> f32 {
+    two_finger_gesture().map_or(1.0, |(zoom, _)| zoom)
+}
+
+/// How far, in pixels, the point between two fingers moved since the last frame.
+///
+/// Returns zero unless exactly two fingers are touching the screen.
+pub fn two_finger_pan_delta() -> Vec2 {
+    two_finger_gesture().map_or(Vec2::ZERO, |(_, pan)| pan)
+}
+
+fn two_finger_gesture() -> Option<(f32, Vec2)> {
+    let context = get_context();
+    if context.touches.len() != 2 {
+        return None;
+    }
+
+    let mut touches = context.touches.values();
+    let (a, b) = (touches.next()?, touches.next()?);
+    let previous = [
+        *context.last_touches.get(&a.id)?,
+        *context.last_touches.get(&b.id)?,
+    ];
+
+    Some(pinch_and_pan(previous, [a.position, b.position]))
+}
+
+/// (zoom ratio, pan) of two fingers moving from `previous` to `current`.
+fn pinch_and_pan(previous: [Vec2; 2], current: [Vec2; 2]) -> (f32, Vec2) {
+    let previous_distance = previous[0].distance(previous[1]);
+    let zoom = if previous_distance > f32::EPSILON {
+        current[0].distance(current[1]) / previous_distance
+    } else {
+        1.0
+    };
+    let pan = (current[0] + current[1]) / 2.0 - (previous[0] + previous[1]) / 2.0;
+
+    (zoom, pan)
+}
+
+#[test]
+fn pinch_and_pan_of_two_fingers() {
+    let previous = [Vec2::new(0., 0.), Vec2::new(10., 0.)];
+
+    let (zoom, pan) = pinch_and_pan(previous, [Vec2::new(-5., 0.), Vec2::new(15., 0.)]);
+    assert_eq!((zoom, pan), (2.0, Vec2::ZERO));
+
+    let (zoom, pan) = pinch_and_pan(previous, [Vec2::new(3., 4.), Vec2::new(13., 4.)]);
+    assert_eq!((zoom, pan), (1.0, Vec2::new(3., 4.)));
+}
+
+/// Maximum time in seconds between two clicks of a double click, 0.3 by default.
+pub fn set_double_click_interval(seconds: f64) {
+    get_context().double_click_interval = seconds;
+}
+
 /// Convert a position in pixels to a position in the range [-1; 1].
 fn convert_to_local(pixel_pos: Vec2) -> Vec2 {
     Vec2::new(pixel_pos.x / screen_width(), pixel_pos.y / screen_height()) * 2.0
         - Vec2::new(1.0, 1.0)
 }
 
-/// Prevents quit
+/// Prevents quit, same as `set_prevent_quit(true)`.
 pub fn prevent_quit() {
-    get_context().prevent_quit_event = true;
+    set_prevent_quit(true);
+}
+
+/// While enabled, closing the window does not quit the app, it only makes
+/// [is_quit_requested] return true for the next frame. Good for "unsaved changes" prompts:
+///
+/// ```ignore
+/// set_prevent_quit(true);
+/// loop {
+///     if is_quit_requested() {
+///         show_confirm_dialog = true;
+///     }
+///     if show_confirm_dialog && confirmed {
+///         set_prevent_quit(false);
+///         miniquad::window::order_quit();
+///     }
+///     next_frame().await;
+/// }
+/// ```
+pub fn set_prevent_quit(prevent: bool) {
+    get_context().prevent_quit_event = prevent;
 }
 
 /// Detect if quit has been requested
@@ -38,7 +38,7 @@
 
 use miniquad::*;
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::future::Future;
 use std::panic::AssertUnwindSafe;
 use std::pin::Pin;
@@ -55,6 +55,7 @@ pub mod input;
 pub mod material;
 pub mod math;
 pub mod models;
+pub mod noise;
 pub mod shapes;
 pub mod text;
 pub mod texture;
@@ -132,8 +133,157 @@ pub use macroquad_macro::main;
 pub use macroquad_macro::test;
 
 /// Cross platform random generator.
+///
+/// The generator is PCG32, and `rand()` is pure integer math, so the same
+/// `srand(seed)` gives the same sequence of `rand()` values on every platform,
+/// including web. `gen_range()` maps those values through f64, so it is as
+/// reproducible as the platform's IEEE 754 f64 arithmetic.
+/// The sequence is shared with everything else calling `rand` functions,
+/// so for reproducible procedural generation keep a separate `RandGenerator`.
 pub mod rand {
     pub use quad_rand::*;
+
+    use crate::math::{polar_to_cartesian, Vec2};
+
+    /// Random index into `weights`, each index picked with probability proportional to its weight.
+    ///
+    /// Returns None if there are no positive weights to pick from.
+    pub fn try_weighted_choice(weights: &[f32]) -> Option<usize> {
+        let is_valid = |weight: &f32| weight.is_finite() && *weight > 0.;
+        let total: f32 = weights.iter().copied().filter(is_valid).sum();
+        if total <= 0. {
+            return None;
+        }
+
+        let mut point = gen_range(0., total);
+        let mut last = None;
+        for (n, weight) in weights.iter().enumerate().filter(|(_, w)| is_valid(w)) {
+            if point < *weight {
+                return Some(n);
+            }
+            point -= weight;
+            last = Some(n);
+        }
+
+        // float rounding may leave a tiny bit of `point` after the last weight
+        last
+    }
+
+    /// Same as `try_weighted_choice`, but panics if there are no positive weights.
+    pub fn weighted_choice(weights: &[f32]) -> usize {
+        try_weighted_choice(weights).expect("weighted_choice: no positive weights")
+    }
+
+    /// Normally distributed random number.
+    pub fn gen_gaussian(mean: f32, std_dev: f32) -> f32 {
+        mean + std_dev * box_muller(rand(), gen_range(0., std::f32::consts::TAU))
+    }
+
+    /// Standard normal sample out of a raw `rand()` value and a random angle.
+    fn box_muller(bits: u32, angle: f32) -> f32 {
+        // (0, 1] to keep ln away from 0, in f64 so no value rounds to the ends
+        let u1 = (bits as f64 + 1.) / 4294967296.;
+
+        (-2. * u1.ln()).sqrt() as f32 * angle.cos()
+    }
+
+    /// Uniformly distributed random point inside a circle centered at (0, 0).
+    pub fn sample_in_circle(radius: f32) -> Vec2 {
+        // sqrt keeps the density uniform, otherwise points clump in the center
+        let rho = gen_range(0., radius * radius).sqrt();
+
+        polar_to_cartesian(rho, gen_range(0., std::f32::consts::TAU))
+    }
+
+    /// Uniformly distributed random point on a circle centered at (0, 0).
+    pub fn sample_on_circle(radius: f32) -> Vec2 {
+        polar_to_cartesian(radius, gen_range(0., std::f32::consts::TAU))
+    }
+
+    /// Random integer in `low..high`, computed without any float math.
+    ///
+    /// `gen_range` goes through `f64` and is deterministic as long as the
+    /// platform follows IEEE 754, which all supported ones do. This one does
+    /// not rely on it at all, for lockstep multiplayer and replays.
+    /// Returns `low` when the range is empty.
+    pub fn gen_range_deterministic(low: i64, high: i64) -> i64 {
+        range_from_bits(low, high, ((rand() as u64) << 32) | rand() as u64)
+    }
+
+    /// [gen_range_deterministic] with a separate generator.
+    pub fn gen_range_deterministic_with_state(
+        generator: &RandGenerator,
+        low: i64,
+        high: i64,
+    ) -> i64 {
+        range_from_bits(
+            low,
+            high,
+            ((generator.rand() as u64) << 32) | generator.rand() as u64,
+        )
+    }
+
+    fn range_from_bits(low: i64, high: i64, bits: u64) -> i64 {
+        if high <= low {
+            return low;
+        }
+        let span = high.abs_diff(low);
+        // multiply-shift maps 64 random bits onto the span without division
+        low.wrapping_add(((bits as u128 * span as u128) >> 64) as i64)
+    }
+
+    #[test]
+    fn gaussian_is_finite_for_every_rand_value() {
+        for bits in [0, 1, u32::MAX - 127, u32::MAX - 1, u32::MAX] {
+            assert!(box_muller(bits, 0.).is_finite());
+        }
+        assert_eq!(box_muller(u32::MAX, 0.), 0.);
+        // ln(2^-32) is the biggest value it can get
+        assert!((box_muller(0, 0.) - (64f32 * 2f32.ln()).sqrt()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn weighted_choice_skips_empty_weights() {
+        assert_eq!(try_weighted_choice(&[]), None);
+        assert_eq!(try_weighted_choice(&[0., 0.]), None);
+        assert_eq!(try_weighted_choice(&[0., -1., 2., f32::NAN]), Some(2));
+    }
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let generator = RandGenerator::new();
+
+        generator.srand(42);
+        let first: Vec<u32> = (0..16).map(|_| generator.rand()).collect();
+        generator.srand(42);
+        let second: Vec<u32> = (0..16).map(|_| generator.rand()).collect();
+
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn sequence_matches_reference() {
+        // PCG32 outputs for seed 12345, the same bits must come out on every platform
+        let generator = RandGenerator::new();
+        generator.srand(12345);
+        let values: Vec<u32> = (0..1000).map(|_| generator.rand()).collect();
+
+        assert_eq!(
+            values[..4],
+            [1411482639, 3165192603, 3360792183, 2433038347]
+        );
+        assert_eq!(values[999], 2328818671);
+        let fold = values
+            .iter()
+            .fold(0u32, |acc, value| acc.rotate_left(5) ^ value);
+        assert_eq!(fold, 0x6c2c8de8);
+
+        generator.srand(12345);
+        let ints: Vec<i64> = (0..8)
+            .map(|_| gen_range_deterministic_with_state(&generator, -10, 10))
+            .collect();
+        assert_eq!(ints, [-4, 5, -8, 1, -5, -4, 8, -4]);
+    }
 }
 
 #[cfg(not(feature = "log-rs"))]
@@ -179,6 +329,7 @@ struct Context {
 
     screen_width: f32,
     screen_height: f32,
+    screen_resized: bool,
 
     simulate_mouse_with_touch: bool,
 
@@ -188,11 +339,17 @@ struct Context {
     mouse_down: HashSet<MouseButton>,
     mouse_pressed: HashSet<MouseButton>,
     mouse_released: HashSet<MouseButton>,
+    mouse_clicks: HashMap<MouseButton, input::ClickState>,
+    double_click_interval: f64,
     touches: HashMap<u64, input::Touch>,
+    // touch positions at the end of the last frame, for gestures
+    last_touches: HashMap<u64, Vec2>,
     chars_pressed_queue: Vec<char>,
     chars_pressed_ui_queue: Vec<char>,
+    chars_typed: Vec<char>,
     mouse_position: Vec2,
     last_mouse_position: Option<Vec2>,
+    mouse_raw_delta: Vec2,
     mouse_wheel: Vec2,
 
     prevent_quit_event: bool,
@@ -214,6 +371,7 @@ struct Context {
     start_time: f64,
     last_frame_time: f64,
     frame_time: f64,
+    frame_time_history: VecDeque<f64>,
 
     #[cfg(one_screenshot)]
     counter: usize,
@@ -231,6 +389,9 @@ struct Context {
     update_on: conf::UpdateTrigger,
 
     dropped_files: Vec<DroppedFile>,
+
+    instanced_pipeline: Option<miniquad::Pipeline>,
+    indexed_material: Option<material::Material>,
 }
 
 #[derive(Clone)]
@@ -301,6 +462,7 @@ impl MiniquadInputEvent {
 
 impl Context {
     const DEFAULT_BG_COLOR: Color = BLACK;
+    const FRAME_TIME_HISTORY_SIZE: usize = 60;
 
     fn new(
         update_on: conf::UpdateTrigger,
@@ -315,6 +477,7 @@ impl Context {
         Context {
             screen_width,
             screen_height,
+            screen_resized: false,
 
             simulate_mouse_with_touch: true,
 
@@ -323,12 +486,17 @@ impl Context {
             keys_released: HashSet::new(),
             chars_pressed_queue: Vec::new(),
             chars_pressed_ui_queue: Vec::new(),
+            chars_typed: Vec::new(),
             mouse_down: HashSet::new(),
             mouse_pressed: HashSet::new(),
             mouse_released: HashSet::new(),
+            mouse_clicks: HashMap::new(),
+            double_click_interval: 0.3,
             touches: HashMap::new(),
+            last_touches: HashMap::new(),
             mouse_position: vec2(0., 0.),
             last_mouse_position: None,
+            mouse_raw_delta: vec2(0., 0.),
             mouse_wheel: vec2(0., 0.),
 
             prevent_quit_event: false,
@@ -358,6 +526,7 @@ impl Context {
             start_time: miniquad::date::now(),
             last_frame_time: miniquad::date::now(),
             frame_time: 1. / 60.,
+            frame_time_history: VecDeque::with_capacity(Self::FRAME_TIME_HISTORY_SIZE),
 
             #[cfg(one_screenshot)]
             counter: 0,
@@ -371,6 +540,9 @@ impl Context {
             update_on,
 
             dropped_files: Vec::new(),
+
+            instanced_pipeline: None,
+            indexed_material: None,
         }
     }
 
@@ -413,6 +585,7 @@ impl Context {
         self.ui_context.draw(get_quad_context(), &mut self.gl);
         let screen_mat = self.pixel_perfect_projection_matrix();
         self.gl.draw(get_quad_context(), screen_mat);
+        self.gl.end_frame();
 
         get_quad_context().commit_frame();
 
@@ -428,13 +601,16 @@ impl Context {
         telemetry::end_gpu_query();
 
         self.mouse_wheel = Vec2::new(0., 0.);
+        self.mouse_raw_delta = Vec2::new(0., 0.);
         self.keys_pressed.clear();
         self.keys_released.clear();
         self.mouse_pressed.clear();
         self.mouse_released.clear();
+        self.chars_typed.clear();
         self.last_mouse_position = Some(crate::prelude::mouse_position_local());
 
         self.quit_requested = false;
+        self.screen_resized = false;
 
         self.textures.garbage_collect(get_quad_context());
 
@@ -443,6 +619,12 @@ impl Context {
             touch.phase != input::TouchPhase::Ended && touch.phase != input::TouchPhase::Cancelled
         });
 
+        self.last_touches = self
+            .touches
+            .values()
+            .map(|touch| (touch.id, touch.position))
+            .collect();
+
         // change all Started or Moved touches to Stationary
         for touch in self.touches.values_mut() {
             if touch.phase == input::TouchPhase::Started || touch.phase == input::TouchPhase::Moved
@@ -454,6 +636,13 @@ impl Context {
         self.dropped_files.clear();
     }
 
+    fn push_frame_time(&mut self) {
+        if self.frame_time_history.len() == Self::FRAME_TIME_HISTORY_SIZE {
+            self.frame_time_history.pop_front();
+        }
+        self.frame_time_history.push_back(self.frame_time);
+    }
+
     pub(crate) fn pixel_perfect_projection_matrix(&self) -> glam::Mat4 {
         let (width, height) = miniquad::window::screen_size();
 
@@ -515,6 +704,7 @@ impl EventHandler for Stage {
         let _z = telemetry::ZoneGuard::new("Event::resize_event");
         get_context().screen_width = width;
         get_context().screen_height = height;
+        get_context().screen_resized = true;
 
         if miniquad::window::blocking_event_loop() {
             miniquad::window::schedule_update();
@@ -524,6 +714,8 @@ impl EventHandler for Stage {
     fn raw_mouse_motion(&mut self, x: f32, y: f32) {
         let context = get_context();
 
+        context.mouse_raw_delta += Vec2::new(x, y);
+
         if context.cursor_grabbed {
             context.mouse_position += Vec2::new(x, y);
 
@@ -577,6 +769,13 @@ impl EventHandler for Stage {
         context.mouse_down.insert(btn);
         context.mouse_pressed.insert(btn);
 
+        let interval = context.double_click_interval;
+        context
+            .mouse_clicks
+            .entry(btn)
+            .or_default()
+            .click(Vec2::new(x, y), date::now(), interval);
+
         context
             .input_events
             .iter_mut()
@@ -649,6 +848,7 @@ impl EventHandler for Stage {
 
         context.chars_pressed_queue.push(character);
         context.chars_pressed_ui_queue.push(character);
+        context.chars_typed.push(character);
 
         context.input_events.iter_mut().for_each(|arr| {
             arr.push(MiniquadInputEvent::Char {
@@ -767,6 +967,7 @@ impl EventHandler for Stage {
             }
             get_context().frame_time = date::now() - get_context().last_frame_time;
             get_context().last_frame_time = date::now();
+            get_context().push_frame_time();
 
             #[cfg(any(target_arch = "wasm32", target_os = "linux"))]
             {
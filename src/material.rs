@@ -89,7 +89,12 @@ pub fn gl_use_default_material() {
     get_context().gl.pipeline(None);
 }
 
-#[doc(hidden)]
+/// GLSL `#include "name"` support, to share snippets between shaders.
+///
+/// ```ignore
+/// let config = PreprocessorConfig::default().include("noise.glsl", NOISE_GLSL);
+/// let fragment = try_preprocess_shader(FRAGMENT, &config)?;
+/// ```
 pub mod shaders {
     type IncludeFilename = String;
     type IncludeContent = String;
@@ -104,67 +109,94 @@ pub mod shaders {
         }
     }
 
-    impl PreprocessorConfig {}
+    impl PreprocessorConfig {
+        /// Register a file for `#include "name"` directives.
+        pub fn include(mut self, name: &str, content: &str) -> PreprocessorConfig {
+            self.includes.push((name.to_string(), content.to_string()));
+            self
+        }
+    }
 
-    pub fn preprocess_shader(source: &str, config: &PreprocessorConfig) -> String {
-        let mut res = source.chars().collect::<Vec<_>>();
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum PreprocessorError {
+        /// `#include` not followed by a quoted file name.
+        MalformedInclude,
+        /// The file is not on the `includes` list.
+        IncludeNotFound(String),
+        /// The file ends up including itself, directly or through other includes.
+        CircularInclude(String),
+    }
 
-        fn find(data: &[char], n: &mut usize, target: &str) -> bool {
-            if *n >= data.len() {
-                return false;
-            }
-            let target = target.chars().collect::<Vec<_>>();
-
-            'outer: for i in *n..data.len() {
-                for j in 0..target.len() {
-                    if data[i + j] != target[j] {
-                        *n += 1;
-                        continue 'outer;
-                    }
+    impl std::fmt::Display for PreprocessorError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                PreprocessorError::MalformedInclude => {
+                    write!(f, "#include should be followed by a \"quoted\" file name")
+                }
+                PreprocessorError::IncludeNotFound(name) => {
+                    write!(f, "Include file {name} in not on \"includes\" list")
+                }
+                PreprocessorError::CircularInclude(name) => {
+                    write!(f, "Include file {name} includes itself")
                 }
-                return true;
             }
-            false
         }
+    }
+
+    impl std::error::Error for PreprocessorError {}
 
-        fn skip_character(data: &[char], n: &mut usize, target: char) {
-            while *n < data.len() && data[*n] == target {
-                *n += 1;
+    /// Replace all `#include "name"` directives with the content from `config`, recursively.
+    ///
+    /// Panics on errors, see `try_preprocess_shader`.
+    pub fn preprocess_shader(source: &str, config: &PreprocessorConfig) -> String {
+        try_preprocess_shader(source, config).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Replace all `#include "name"` directives with the content from `config`, recursively.
+    pub fn try_preprocess_shader(
+        source: &str,
+        config: &PreprocessorConfig,
+    ) -> Result<String, PreprocessorError> {
+        fn expand(
+            source: &str,
+            config: &PreprocessorConfig,
+            stack: &mut Vec<String>,
+        ) -> Result<String, PreprocessorError> {
+            let mut res = String::with_capacity(source.len());
+            let mut rest = source;
+
+            while let Some(directive_start) = rest.find("#include") {
+                res.push_str(&rest[..directive_start]);
+
+                let filename = rest[directive_start + "#include".len()..].trim_start_matches(' ');
+                let filename = filename
+                    .strip_prefix('"')
+                    .ok_or(PreprocessorError::MalformedInclude)?;
+                let filename_end = filename
+                    .find('"')
+                    .ok_or(PreprocessorError::MalformedInclude)?;
+                rest = &filename[filename_end + 1..];
+                let filename = &filename[..filename_end];
+
+                if stack.iter().any(|name| name == filename) {
+                    return Err(PreprocessorError::CircularInclude(filename.to_string()));
+                }
+                let (_, include_content) = config
+                    .includes
+                    .iter()
+                    .find(|(name, _)| name == filename)
+                    .ok_or_else(|| PreprocessorError::IncludeNotFound(filename.to_string()))?;
+
+                stack.push(filename.to_string());
+                res.push_str(&expand(include_content, config, stack)?);
+                stack.pop();
             }
-        }
+            res.push_str(rest);
 
-        let mut i = 0;
-        while find(&res, &mut i, "#include") {
-            let directive_start_ix = i;
-            i += "#include".len();
-            skip_character(&res, &mut i, ' ');
-            assert!(res[i] == '\"');
-            i += 1;
-            let filename_start_ix = i;
-            find(&res, &mut i, "\"");
-            let filename_end_ix = i;
-            let filename = res[filename_start_ix..filename_end_ix]
-                .iter()
-                .cloned()
-                .collect::<String>();
-
-            let include_content = config
-                .includes
-                .iter()
-                .find(|(name, _)| name == &filename)
-                .expect(&format!(
-                    "Include file {filename} in not on \"includes\" list"
-                ));
-
-            let _ = res
-                .splice(
-                    directive_start_ix..filename_end_ix + 1,
-                    include_content.1.chars(),
-                )
-                .collect::<Vec<_>>();
+            Ok(res)
         }
 
-        res.into_iter().collect()
+        expand(source, config, &mut vec![])
     }
 
     #[test]
@@ -202,4 +234,27 @@ qwe
 
         assert_eq!(result, preprocessed);
     }
+
+    #[test]
+    fn preprocessor_nested_and_circular_includes() {
+        let config = PreprocessorConfig::default()
+            .include("a.glsl", "a(#include \"b.glsl\")")
+            .include("b.glsl", "b");
+        assert_eq!(
+            try_preprocess_shader("#include \"a.glsl\";", &config),
+            Ok("a(b);".to_string())
+        );
+
+        let config = PreprocessorConfig::default()
+            .include("a.glsl", "#include \"b.glsl\"")
+            .include("b.glsl", "#include \"a.glsl\"");
+        assert_eq!(
+            try_preprocess_shader("#include \"a.glsl\"", &config),
+            Err(PreprocessorError::CircularInclude("a.glsl".to_string()))
+        );
+        assert_eq!(
+            try_preprocess_shader("#include \"c.glsl\"", &config),
+            Err(PreprocessorError::IncludeNotFound("c.glsl".to_string()))
+        );
+    }
 }
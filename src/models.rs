@@ -1,10 +1,16 @@
 //! 3D shapes and models, loading 3d models from files, drawing 3D primitives.
 
-use crate::{color::Color, get_context};
+use crate::{camera::Camera3D, color::Color, get_context};
 
 use crate::{quad_gl::DrawMode, texture::Texture2D};
 use glam::{vec2, vec3, vec4, Quat, Vec2, Vec3, Vec4};
 
+mod instanced;
+mod obj;
+
+pub use instanced::InstancedMesh;
+pub use obj::load_obj;
+
 #[repr(C)]
 #[derive(Clone, Debug, Copy)]
 pub struct Vertex {
@@ -52,6 +58,169 @@ pub fn draw_mesh(mesh: &Mesh) {
     context.gl.geometry(&mesh.vertices[..], &mesh.indices[..]);
 }
 
+fn mesh_vertex(position: Vec3, uv: Vec2, normal: Vec3) -> Vertex {
+    Vertex {
+        position,
+        uv,
+        color: Color::new(1., 1., 1., 1.).into(),
+        normal: normal.extend(0.),
+    }
+}
+
+/// Box of the given size centered on the origin, with its own 4 vertices
+/// per face so the normals are flat.
+///
+/// The generated meshes are white, with unit normals in `Vertex::normal`
+/// and counter clockwise triangles seen from the outside.
+/// [draw_mesh] draws at most one draw call worth of geometry, 5000 indices by default.
+pub fn gen_cube(size: Vec3) -> Mesh {
+    let half = size / 2.;
+    let mut vertices = Vec::with_capacity(24);
+    let mut indices = Vec::with_capacity(36);
+
+    // normal, then the face u and v axis, with u x v = normal
+    for (normal, u, v) in [
+        (Vec3::X, -Vec3::Z, Vec3::Y),
+        (-Vec3::X, Vec3::Z, Vec3::Y),
+        (Vec3::Y, Vec3::X, -Vec3::Z),
+        (-Vec3::Y, Vec3::X, Vec3::Z),
+        (Vec3::Z, Vec3::X, Vec3::Y),
+        (-Vec3::Z, -Vec3::X, Vec3::Y),
+    ] {
+        let first = vertices.len() as u16;
+        for (s, t) in [(-1., -1.), (1., -1.), (1., 1.), (-1., 1.)] {
+            vertices.push(mesh_vertex(
+                (normal + u * s + v * t) * half,
+                vec2((s + 1.) / 2., (1. - t) / 2.),
+                normal,
+            ));
+        }
+        indices.extend([0, 1, 2, 0, 2, 3].map(|i| first + i));
+    }
+
+    Mesh {
+        vertices,
+        indices,
+        texture: None,
+    }
+}
+
+/// UV sphere centered on the origin, `rings` from pole to pole and `sectors` around.
+/// The texture wraps around once, the seam is at +X. See [gen_cube] for the conventions.
+pub fn gen_sphere(radius: f32, rings: u32, sectors: u32) -> Mesh {
+    assert!(rings >= 2 && sectors >= 3);
+    assert!((rings + 1) * (sectors + 1) <= u16::MAX as u32);
+
+    let mut vertices = Vec::with_capacity(((rings + 1) * (sectors + 1)) as usize);
+    for ring in 0..=rings {
+        let phi = std::f32::consts::PI * ring as f32 / rings as f32;
+        for sector in 0..=sectors {
+            let theta = std::f32::consts::PI * 2. * sector as f32 / sectors as f32;
+            let normal = vec3(phi.sin() * theta.cos(), phi.cos(), phi.sin() * theta.sin());
+            let uv = vec2(sector as f32 / sectors as f32, ring as f32 / rings as f32);
+            vertices.push(mesh_vertex(normal * radius, uv, normal));
+        }
+    }
+
+    let mut indices = Vec::with_capacity((rings * sectors * 6) as usize);
+    for ring in 0..rings {
+        for sector in 0..sectors {
+            let a = (ring * (sectors + 1) + sector) as u16;
+            let b = a + sectors as u16 + 1;
+            indices.extend([a, a + 1, b, a + 1, b + 1, b]);
+        }
+    }
+
+    Mesh {
+        vertices,
+        indices,
+        texture: None,
+    }
+}
+
+/// Flat plane on XZ facing +Y, centered on the origin and split in
+/// `subdivisions` x `subdivisions` quads. See [gen_cube] for the conventions.
+pub fn gen_plane(size: Vec2, subdivisions: u32) -> Mesh {
+    assert!(subdivisions >= 1);
+    assert!((subdivisions + 1) * (subdivisions + 1) <= u16::MAX as u32);
+
+    let row = subdivisions + 1;
+    let mut vertices = Vec::with_capacity((row * row) as usize);
+    for j in 0..row {
+        for i in 0..row {
+            let uv = vec2(i as f32, j as f32) / subdivisions as f32;
+            let position = vec3((uv.x - 0.5) * size.x, 0., (uv.y - 0.5) * size.y);
+            vertices.push(mesh_vertex(position, uv, Vec3::Y));
+        }
+    }
+
+    let mut indices = Vec::with_capacity((subdivisions * subdivisions * 6) as usize);
+    for j in 0..subdivisions {
+        for i in 0..subdivisions {
+            let a = (j * row + i) as u16;
+            let (b, c, d) = (a + 1, a + row as u16 + 1, a + row as u16);
+            indices.extend([a, c, b, a, d, c]);
+        }
+    }
+
+    Mesh {
+        vertices,
+        indices,
+        texture: None,
+    }
+}
+
+/// Closed cylinder along Y, centered on the origin, with `sectors` sides.
+/// Side and caps have separate vertices, so the edges stay sharp. See [gen_cube] for the conventions.
+pub fn gen_cylinder(radius: f32, height: f32, sectors: u32) -> Mesh {
+    assert!(sectors >= 3);
+    assert!((sectors + 1) * 4 + 2 <= u16::MAX as u32);
+
+    let around = |sector: u32| {
+        let theta = std::f32::consts::PI * 2. * sector as f32 / sectors as f32;
+        vec3(theta.cos(), 0., theta.sin())
+    };
+    let half = Vec3::Y * height / 2.;
+
+    let mut vertices = Vec::with_capacity(((sectors + 1) * 4 + 2) as usize);
+    let mut indices = Vec::with_capacity((sectors * 12) as usize);
+
+    for sector in 0..=sectors {
+        let normal = around(sector);
+        let u = sector as f32 / sectors as f32;
+        vertices.push(mesh_vertex(normal * radius - half, vec2(u, 1.), normal));
+        vertices.push(mesh_vertex(normal * radius + half, vec2(u, 0.), normal));
+    }
+    for sector in 0..sectors as u16 {
+        let (bottom, top) = (sector * 2, sector * 2 + 1);
+        indices.extend([bottom, top, bottom + 2, bottom + 2, top, top + 2]);
+    }
+
+    for normal in [Vec3::Y, -Vec3::Y] {
+        let center = vertices.len() as u16;
+        vertices.push(mesh_vertex(normal * height / 2., vec2(0.5, 0.5), normal));
+        for sector in 0..=sectors {
+            let dir = around(sector);
+            let uv = vec2(0.5 + dir.x / 2., 0.5 + dir.z / 2.);
+            vertices.push(mesh_vertex(dir * radius + normal * height / 2., uv, normal));
+        }
+        for sector in 0..sectors as u16 {
+            let (a, b) = (center + 1 + sector, center + 2 + sector);
+            if normal.y > 0. {
+                indices.extend([center, b, a]);
+            } else {
+                indices.extend([center, a, b]);
+            }
+        }
+    }
+
+    Mesh {
+        vertices,
+        indices,
+        texture: None,
+    }
+}
+
 fn draw_quad(vertices: [Vertex; 4]) {
     let context = get_context();
     let indices = [0, 1, 2, 0, 2, 3];
@@ -167,6 +336,51 @@ pub fn draw_affine_parallelogram(
     draw_quad([v1, v2, v3, v4]);
 }
 
+/// How a billboard turns to face the camera.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BillboardMode {
+    /// Always faces the camera, like a sprite glued to the screen.
+    /// Good for particles, icons and health bars.
+    Spherical,
+    /// Only rotates around the camera's up axis and stays upright.
+    /// Good for trees and characters standing on the ground.
+    Cylindrical,
+}
+
+/// Draw a camera facing rectangle, centered at `position`.
+///
+/// `camera` should be the one currently used for drawing.
+/// Billboards with the same texture are batched into a single draw call.
+pub fn draw_billboard(
+    camera: &Camera3D,
+    position: Vec3,
+    size: Vec2,
+    texture: Option<&Texture2D>,
+    mode: BillboardMode,
+    color: Color,
+) {
+    let forward = (camera.target - camera.position).normalize_or_zero();
+    let right = forward.cross(camera.up).normalize_or_zero();
+    let up = match mode {
+        BillboardMode::Spherical => right.cross(forward),
+        BillboardMode::Cylindrical => camera.up.normalize_or_zero(),
+    };
+
+    let right = right * size.x / 2.;
+    let up = up * size.y / 2.;
+
+    let v1 = Vertex::new2(position - right + up, vec2(0., 0.), color);
+    let v2 = Vertex::new2(position + right + up, vec2(1., 0.), color);
+    let v3 = Vertex::new2(position + right - up, vec2(1., 1.), color);
+    let v4 = Vertex::new2(position - right - up, vec2(0., 1.), color);
+
+    {
+        let context = get_context();
+        context.gl.texture(texture);
+    }
+    draw_quad([v1, v2, v3, v4]);
+}
+
 /// Draw an affine (3D) parallelepiped at given position, using six parallelograms.
 ///
 /// The drawn parallelepiped will be built from the followwing parallelograms:
@@ -728,3 +942,28 @@ pub fn draw_cylinder_ex(
         );
     }
 }
+
+#[test]
+fn generated_meshes_face_outwards() {
+    let check = |mesh: Mesh, vertices: usize, indices: usize| {
+        assert_eq!(mesh.vertices.len(), vertices);
+        assert_eq!(mesh.indices.len(), indices);
+
+        for vertex in &mesh.vertices {
+            assert!((vertex.normal.truncate().length() - 1.).abs() < 0.0001);
+        }
+        for triangle in mesh.indices.chunks(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| mesh.vertices[triangle[i] as usize]);
+            let face = (b.position - a.position).cross(c.position - a.position);
+            // the sphere poles have degenerate triangles
+            if face.length() > 0.0001 {
+                assert!(face.dot((a.normal + b.normal + c.normal).truncate()) > 0.);
+            }
+        }
+    };
+
+    check(gen_cube(vec3(1., 2., 3.)), 24, 36);
+    check(gen_sphere(2., 8, 16), 9 * 17, 8 * 16 * 6);
+    check(gen_plane(vec2(4., 4.), 3), 16, 54);
+    check(gen_cylinder(1., 2., 12), 13 * 4 + 2, 12 * 12);
+}
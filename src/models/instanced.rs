@@ -0,0 +1,265 @@
+//! One mesh drawn many times in a single draw call, for forests and voxel chunks.
+
+use crate::{
+    get_context, logging::warn, models::Mesh, texture::Texture2D, window::get_internal_gl,
+};
+
+use glam::Mat4;
+use miniquad::{
+    Backend, Bindings, BufferId, BufferLayout, BufferSource, BufferType, BufferUsage, Comparison,
+    PassAction, Pipeline, PipelineParams, RenderingBackend, ShaderMeta, ShaderSource,
+    UniformBlockLayout, UniformDesc, UniformType, UniformsSource, VertexAttribute, VertexFormat,
+    VertexStep,
+};
+
+/// [Mesh] uploaded to the GPU once, then drawn with a transform per instance.
+///
+/// ```ignore
+/// let trees = InstancedMesh::new(&tree_mesh, 10000);
+/// loop {
+///     set_camera(&camera);
+///     trees.draw(&transforms);
+///     next_frame().await;
+/// }
+/// ```
+///
+/// Each draw flushes the batched geometry drawn so far, like any raw miniquad call.
+pub struct InstancedMesh {
+    vertex_buffer: BufferId,
+    index_buffer: BufferId,
+    instance_buffer: BufferId,
+    indices: i32,
+    max_instances: usize,
+    pub texture: Option<Texture2D>,
+}
+
+impl InstancedMesh {
+    /// Upload `mesh`, with room for `max_instances` transforms per draw.
+    pub fn new(mesh: &Mesh, max_instances: usize) -> InstancedMesh {
+        let ctx = get_context().quad_context.as_mut();
+
+        let vertex_buffer = ctx.new_buffer(
+            BufferType::VertexBuffer,
+            BufferUsage::Immutable,
+            BufferSource::slice(&mesh.vertices),
+        );
+        let index_buffer = ctx.new_buffer(
+            BufferType::IndexBuffer,
+            BufferUsage::Immutable,
+            BufferSource::slice(&mesh.indices),
+        );
+        // rewritten on every draw
+        let instance_buffer = ctx.new_buffer(
+            BufferType::VertexBuffer,
+            BufferUsage::Stream,
+            BufferSource::empty::<Mat4>(max_instances),
+        );
+
+        InstancedMesh {
+            vertex_buffer,
+            index_buffer,
+            instance_buffer,
+            indices: mesh.indices.len() as i32,
+            max_instances,
+            texture: mesh.texture.clone(),
+        }
+    }
+
+    /// Draw the mesh once per model matrix in `transforms`, with the current camera.
+    pub fn draw(&self, transforms: &[Mat4]) {
+        if transforms.len() > self.max_instances {
+            warn!("InstancedMesh::draw exceeded max_instances, clamping");
+        }
+        let transforms = &transforms[..transforms.len().min(self.max_instances)];
+        if transforms.is_empty() {
+            return;
+        }
+
+        let pipeline = instanced_pipeline();
+        let texture = {
+            let context = get_context();
+            self.texture
+                .as_ref()
+                .map_or(context.gl.white_texture, |texture| {
+                    context.raw_miniquad_id(&texture.texture)
+                })
+        };
+
+        let mut gl = unsafe { get_internal_gl() };
+        gl.flush();
+
+        let projection = gl.quad_gl.get_projection_matrix();
+        let render_pass = gl.quad_gl.get_active_render_pass();
+        let viewport = gl.quad_gl.get_viewport_override();
+        let ctx = gl.quad_context;
+
+        ctx.buffer_update(self.instance_buffer, BufferSource::slice(transforms));
+
+        ctx.begin_pass(render_pass, PassAction::Nothing);
+        ctx.apply_pipeline(&pipeline);
+        if let Some((x, y, w, h)) = viewport {
+            ctx.apply_viewport(x, y, w, h);
+        }
+        ctx.apply_bindings(&Bindings {
+            vertex_buffers: vec![self.vertex_buffer, self.instance_buffer],
+            index_buffer: self.index_buffer,
+            images: vec![texture],
+        });
+        ctx.apply_uniforms(UniformsSource::table(&projection));
+        ctx.draw(0, self.indices, transforms.len() as i32);
+        ctx.end_render_pass();
+    }
+}
+
+impl Drop for InstancedMesh {
+    fn drop(&mut self) {
+        let ctx = get_context().quad_context.as_mut();
+        ctx.delete_buffer(self.vertex_buffer);
+        ctx.delete_buffer(self.index_buffer);
+        ctx.delete_buffer(self.instance_buffer);
+    }
+}
+
+/// Shared by all the instanced meshes, built on the first draw.
+fn instanced_pipeline() -> Pipeline {
+    let context = get_context();
+    if let Some(pipeline) = context.instanced_pipeline {
+        return pipeline;
+    }
+
+    let ctx: &mut dyn RenderingBackend = context.quad_context.as_mut();
+    let shader = ctx
+        .new_shader(
+            match ctx.info().backend {
+                Backend::OpenGl => ShaderSource::Glsl {
+                    vertex: shader::VERTEX,
+                    fragment: shader::FRAGMENT,
+                },
+                Backend::Metal => ShaderSource::Msl {
+                    program: shader::METAL,
+                },
+            },
+            shader::meta(),
+        )
+        .unwrap_or_else(|error| panic!("Failed to load instanced mesh shader: {:?}", error));
+
+    let pipeline = ctx.new_pipeline(
+        &[
+            BufferLayout::default(),
+            BufferLayout {
+                step_func: VertexStep::PerInstance,
+                ..Default::default()
+            },
+        ],
+        &[
+            VertexAttribute::with_buffer("position", VertexFormat::Float3, 0),
+            VertexAttribute::with_buffer("texcoord", VertexFormat::Float2, 0),
+            VertexAttribute::with_buffer("color0", VertexFormat::Byte4, 0),
+            VertexAttribute::with_buffer("normal", VertexFormat::Float4, 0),
+            VertexAttribute::with_buffer("model_col0", VertexFormat::Float4, 1),
+            VertexAttribute::with_buffer("model_col1", VertexFormat::Float4, 1),
+            VertexAttribute::with_buffer("model_col2", VertexFormat::Float4, 1),
+            VertexAttribute::with_buffer("model_col3", VertexFormat::Float4, 1),
+        ],
+        shader,
+        PipelineParams {
+            depth_write: true,
+            depth_test: Comparison::LessOrEqual,
+            ..Default::default()
+        },
+    );
+
+    context.instanced_pipeline = Some(pipeline);
+    pipeline
+}
+
+mod shader {
+    use super::*;
+
+    pub const VERTEX: &str = r#"#version 100
+    attribute vec3 position;
+    attribute vec2 texcoord;
+    attribute vec4 color0;
+    attribute vec4 normal;
+    attribute vec4 model_col0;
+    attribute vec4 model_col1;
+    attribute vec4 model_col2;
+    attribute vec4 model_col3;
+
+    varying lowp vec2 uv;
+    varying lowp vec4 color;
+
+    uniform mat4 Projection;
+
+    void main() {
+        mat4 model = mat4(model_col0, model_col1, model_col2, model_col3);
+        gl_Position = Projection * model * vec4(position, 1);
+        color = color0 / 255.0;
+        uv = texcoord;
+    }"#;
+
+    pub const FRAGMENT: &str = r#"#version 100
+    varying lowp vec4 color;
+    varying lowp vec2 uv;
+
+    uniform sampler2D Texture;
+
+    void main() {
+        gl_FragColor = color * texture2D(Texture, uv);
+    }"#;
+
+    pub const METAL: &str = r#"
+#include <metal_stdlib>
+    using namespace metal;
+
+    struct Uniforms
+    {
+        float4x4 Projection;
+    };
+
+    struct Vertex
+    {
+        float3 position    [[attribute(0)]];
+        float2 texcoord    [[attribute(1)]];
+        float4 color0      [[attribute(2)]];
+        float4 normal      [[attribute(3)]];
+        float4 model_col0  [[attribute(4)]];
+        float4 model_col1  [[attribute(5)]];
+        float4 model_col2  [[attribute(6)]];
+        float4 model_col3  [[attribute(7)]];
+    };
+
+    struct RasterizerData
+    {
+        float4 position [[position]];
+        float4 color [[user(locn0)]];
+        float2 uv [[user(locn1)]];
+    };
+
+    vertex RasterizerData vertexShader(Vertex v [[stage_in]], constant Uniforms& uniforms [[buffer(0)]])
+    {
+        RasterizerData out;
+
+        float4x4 model = float4x4(v.model_col0, v.model_col1, v.model_col2, v.model_col3);
+        out.position = uniforms.Projection * model * float4(v.position, 1);
+        out.color = v.color0 / 255.0;
+        out.uv = v.texcoord;
+
+        return out;
+    }
+
+    fragment float4 fragmentShader(RasterizerData in [[stage_in]], texture2d<float> tex [[texture(0)]], sampler texSmplr [[sampler(0)]])
+    {
+        return in.color * tex.sample(texSmplr, in.uv);
+    }
+    "#;
+
+    pub fn meta() -> ShaderMeta {
+        ShaderMeta {
+            images: vec!["Texture".to_string()],
+            uniforms: UniformBlockLayout {
+                uniforms: vec![UniformDesc::new("Projection", UniformType::Mat4)],
+            },
+        }
+    }
+}
@@ -0,0 +1,272 @@
+//! Wavefront OBJ/MTL loading.
+
+use crate::{
+    color::Color,
+    file::load_string,
+    models::{Mesh, Vertex},
+    texture::{load_texture, Texture2D},
+    Error,
+};
+
+use glam::{vec2, vec4, Vec2, Vec3};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone)]
+struct ObjMaterial {
+    diffuse: Color,
+    diffuse_map: Option<String>,
+}
+
+impl Default for ObjMaterial {
+    fn default() -> ObjMaterial {
+        ObjMaterial {
+            diffuse: Color::new(1., 1., 1., 1.),
+            diffuse_map: None,
+        }
+    }
+}
+
+struct ObjMesh {
+    vertices: Vec<Vertex>,
+    indices: Vec<u16>,
+    diffuse_map: Option<String>,
+}
+
+/// Load an OBJ file and the MTL files it refers to.
+///
+/// Returns a [Mesh] per object and material, ready for [draw_mesh](super::draw_mesh):
+/// the material diffuse color is baked into the vertex colors and its diffuse map
+/// becomes the mesh texture. Paths in the OBJ file are relative to the OBJ file.
+///
+/// Faces with more than 3 vertices are split into triangles,
+/// vertices without normals get a zero normal.
+pub async fn load_obj(path: &str) -> Result<Vec<Mesh>, Error> {
+    let source = load_string(path).await?;
+    let dir = path.rfind('/').map_or("", |slash| &path[..=slash]);
+
+    let mut materials = HashMap::new();
+    for line in source.lines() {
+        if let Some(library) = line.trim().strip_prefix("mtllib") {
+            let library = load_string(&format!("{}{}", dir, library.trim())).await?;
+            materials.extend(parse_mtl(&library));
+        }
+    }
+
+    let mut textures: HashMap<String, Texture2D> = HashMap::new();
+    let mut meshes = vec![];
+    for mesh in parse_obj(&source, &materials)? {
+        let texture = match mesh.diffuse_map {
+            Some(map) => {
+                if !textures.contains_key(&map) {
+                    let texture = load_texture(&format!("{}{}", dir, map)).await?;
+                    textures.insert(map.clone(), texture);
+                }
+                Some(textures[&map].clone())
+            }
+            None => None,
+        };
+        meshes.push(Mesh {
+            vertices: mesh.vertices,
+            indices: mesh.indices,
+            texture,
+        });
+    }
+
+    Ok(meshes)
+}
+
+fn floats<const N: usize>(args: &str) -> [f32; N] {
+    let mut values = args.split_whitespace().map(|x| x.parse().unwrap_or(0.));
+    [(); N].map(|_| values.next().unwrap_or(0.))
+}
+
+fn parse_mtl(source: &str) -> HashMap<String, ObjMaterial> {
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, ObjMaterial)> = None;
+
+    for line in source.lines() {
+        let (keyword, args) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+        match keyword {
+            "newmtl" => {
+                materials.extend(current.take());
+                current = Some((args.trim().to_string(), ObjMaterial::default()));
+            }
+            "Kd" => {
+                if let Some((_, material)) = &mut current {
+                    let [r, g, b] = floats(args);
+                    material.diffuse = Color::new(r, g, b, material.diffuse.a);
+                }
+            }
+            "d" => {
+                if let Some((_, material)) = &mut current {
+                    material.diffuse.a = floats::<1>(args)[0];
+                }
+            }
+            "map_Kd" => {
+                if let Some((_, material)) = &mut current {
+                    // options like "-bm 1" may come first, the file name is last
+                    material.diffuse_map = args.split_whitespace().last().map(str::to_string);
+                }
+            }
+            _ => {}
+        }
+    }
+    materials.extend(current);
+
+    materials
+}
+
+fn parse_obj(
+    source: &str,
+    materials: &HashMap<String, ObjMaterial>,
+) -> Result<Vec<ObjMesh>, Error> {
+    let mut positions: Vec<Vec3> = vec![];
+    let mut uvs: Vec<Vec2> = vec![];
+    let mut normals: Vec<Vec3> = vec![];
+
+    let mut meshes = vec![];
+    let mut mesh = ObjMesh {
+        vertices: vec![],
+        indices: vec![],
+        diffuse_map: None,
+    };
+    let mut material = ObjMaterial::default();
+    let mut unique: HashMap<(usize, Option<usize>, Option<usize>), u16> = HashMap::new();
+
+    // 1-based, negative ones count from the end
+    let resolve = |index: &str, len: usize| -> Result<Option<usize>, Error> {
+        if index.is_empty() {
+            return Ok(None);
+        }
+        let index: i64 = index
+            .parse()
+            .map_err(|_| Error::ModelError("Invalid OBJ face index"))?;
+        let resolved = if index < 0 {
+            len as i64 + index
+        } else {
+            index - 1
+        };
+        if resolved < 0 || resolved >= len as i64 {
+            return Err(Error::ModelError("OBJ face refers to a missing vertex"));
+        }
+        Ok(Some(resolved as usize))
+    };
+
+    for line in source.lines() {
+        let (keyword, args) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+        match keyword {
+            "v" => positions.push(floats::<3>(args).into()),
+            "vt" => {
+                let [u, v] = floats(args);
+                // OBJ has v going up, textures start at the top
+                uvs.push(vec2(u, 1. - v));
+            }
+            "vn" => normals.push(Vec3::from(floats::<3>(args)).normalize_or_zero()),
+            "o" | "g" | "usemtl" => {
+                if keyword == "usemtl" {
+                    material = materials.get(args.trim()).cloned().unwrap_or_default();
+                }
+                if !mesh.indices.is_empty() {
+                    meshes.push(mesh);
+                }
+                mesh = ObjMesh {
+                    vertices: vec![],
+                    indices: vec![],
+                    diffuse_map: material.diffuse_map.clone(),
+                };
+                unique.clear();
+            }
+            "f" => {
+                let mut face = vec![];
+                for corner in args.split_whitespace() {
+                    let mut parts = corner.split('/');
+                    let position = resolve(parts.next().unwrap_or(""), positions.len())?
+                        .ok_or(Error::ModelError("OBJ face vertex without a position"))?;
+                    let uv = resolve(parts.next().unwrap_or(""), uvs.len())?;
+                    let normal = resolve(parts.next().unwrap_or(""), normals.len())?;
+
+                    let key = (position, uv, normal);
+                    let index = match unique.get(&key) {
+                        Some(index) => *index,
+                        None => {
+                            if mesh.vertices.len() > u16::MAX as usize {
+                                return Err(Error::ModelError("OBJ object has too many vertices"));
+                            }
+                            mesh.vertices.push(Vertex {
+                                position: positions[position],
+                                uv: uv.map_or(Vec2::ZERO, |uv| uvs[uv]),
+                                color: material.diffuse.into(),
+                                normal: normal.map_or(vec4(0., 0., 0., 0.), |normal| {
+                                    normals[normal].extend(0.)
+                                }),
+                            });
+                            let index = (mesh.vertices.len() - 1) as u16;
+                            unique.insert(key, index);
+                            index
+                        }
+                    };
+                    face.push(index);
+                }
+                for i in 1..face.len().saturating_sub(1) {
+                    mesh.indices.extend([face[0], face[i], face[i + 1]]);
+                }
+            }
+            _ => {}
+        }
+    }
+    if !mesh.indices.is_empty() {
+        meshes.push(mesh);
+    }
+
+    Ok(meshes)
+}
+
+#[test]
+fn parse_objects_and_materials() {
+    let mtl = "
+newmtl red
+Kd 1 0 0
+newmtl brick
+Kd 1 1 1
+map_Kd -bm 1 textures/brick.png
+";
+    let obj = "
+mtllib scene.mtl
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 1
+vn 0 0 2
+
+o quad
+usemtl red
+f 1/1/1 2/1/1 3/2/1 4/2/1
+
+o triangle
+usemtl brick
+f -4 -3 -2
+";
+    let materials = parse_mtl(mtl);
+    assert_eq!(materials.len(), 2);
+
+    let meshes = parse_obj(obj, &materials).unwrap();
+    assert_eq!(meshes.len(), 2);
+
+    let quad = &meshes[0];
+    assert_eq!(quad.vertices.len(), 4);
+    assert_eq!(quad.indices, [0, 1, 2, 0, 2, 3]);
+    assert_eq!(quad.vertices[0].color, [255, 0, 0, 255]);
+    assert_eq!(quad.vertices[0].uv, vec2(0., 1.));
+    assert_eq!(quad.vertices[2].uv, vec2(1., 0.));
+    assert_eq!(quad.vertices[0].normal, vec4(0., 0., 1., 0.));
+    assert_eq!(quad.diffuse_map, None);
+
+    let triangle = &meshes[1];
+    assert_eq!(triangle.indices.len(), 3);
+    assert_eq!(triangle.vertices[2].position, glam::vec3(1., 1., 0.));
+    assert_eq!(triangle.diffuse_map.as_deref(), Some("textures/brick.png"));
+
+    assert!(parse_obj("v 0 0 0\nf 1 2 3", &materials).is_err());
+}
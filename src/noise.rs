@@ -0,0 +1,187 @@
+//! Deterministic coherent noise for procedural generation.
+//!
+//! Every function takes an explicit `seed` and only uses integer hashing and
+//! plain float math, so the same seed gives the same world on every platform.
+//! To get a different world each run, seed it from [crate::rand]:
+//! ```ignore
+//! let seed = rand::rand();
+//! let height = fbm(x * 0.01, y * 0.01, 5, 2.0, 0.5, seed);
+//! ```
+
+fn hash(mut h: u32) -> u32 {
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x7feb_352d);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x846c_a68b);
+    h ^= h >> 16;
+    h
+}
+
+fn hash_2d(x: i32, y: i32, seed: u32) -> u32 {
+    hash(x as u32 ^ hash(y as u32 ^ hash(seed)))
+}
+
+fn hash_3d(x: i32, y: i32, z: i32, seed: u32) -> u32 {
+    hash(x as u32 ^ hash(y as u32 ^ hash(z as u32 ^ hash(seed))))
+}
+
+// quintic smoothstep, keeps the second derivative continuous on cell borders
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6. - 15.) + 10.)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+fn gradient_2d(hash: u32, x: f32, y: f32) -> f32 {
+    match hash & 7 {
+        0 => x + y,
+        1 => x - y,
+        2 => -x + y,
+        3 => -x - y,
+        4 => x,
+        5 => -x,
+        6 => y,
+        _ => -y,
+    }
+}
+
+fn gradient_3d(hash: u32, x: f32, y: f32, z: f32) -> f32 {
+    // the 12 cube edge directions, the last 4 repeated to fill 16
+    match hash & 15 {
+        0 | 12 => x + y,
+        1 | 13 => -x + y,
+        2 => x - y,
+        3 => -x - y,
+        4 => x + z,
+        5 => -x + z,
+        6 => x - z,
+        7 => -x - z,
+        8 => y + z,
+        9 | 14 => -y + z,
+        10 => y - z,
+        _ => -y - z,
+    }
+}
+
+/// 2D Perlin gradient noise, roughly in [-1, 1] and 0 on integer coordinates.
+pub fn perlin_2d(x: f32, y: f32, seed: u32) -> f32 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let (ix, iy) = (x0 as i32, y0 as i32);
+
+    let corner = |dx: i32, dy: i32| {
+        gradient_2d(
+            hash_2d(ix.wrapping_add(dx), iy.wrapping_add(dy), seed),
+            fx - dx as f32,
+            fy - dy as f32,
+        )
+    };
+
+    let (u, v) = (fade(fx), fade(fy));
+    lerp(
+        lerp(corner(0, 0), corner(1, 0), u),
+        lerp(corner(0, 1), corner(1, 1), u),
+        v,
+    )
+    .clamp(-1., 1.)
+}
+
+/// 3D Perlin gradient noise, roughly in [-1, 1] and 0 on integer coordinates.
+pub fn perlin_3d(x: f32, y: f32, z: f32, seed: u32) -> f32 {
+    let (x0, y0, z0) = (x.floor(), y.floor(), z.floor());
+    let (fx, fy, fz) = (x - x0, y - y0, z - z0);
+    let (ix, iy, iz) = (x0 as i32, y0 as i32, z0 as i32);
+
+    let corner = |dx: i32, dy: i32, dz: i32| {
+        gradient_3d(
+            hash_3d(
+                ix.wrapping_add(dx),
+                iy.wrapping_add(dy),
+                iz.wrapping_add(dz),
+                seed,
+            ),
+            fx - dx as f32,
+            fy - dy as f32,
+            fz - dz as f32,
+        )
+    };
+
+    let (u, v, w) = (fade(fx), fade(fy), fade(fz));
+    let layer = |dz: i32| {
+        lerp(
+            lerp(corner(0, 0, dz), corner(1, 0, dz), u),
+            lerp(corner(0, 1, dz), corner(1, 1, dz), u),
+            v,
+        )
+    };
+    lerp(layer(0), layer(1), w).clamp(-1., 1.)
+}
+
+/// 2D value noise: random values in [-1, 1] on integer coordinates, smoothly interpolated.
+/// Blobbier than Perlin noise and a bit cheaper.
+pub fn value_noise(x: f32, y: f32, seed: u32) -> f32 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (ix, iy) = (x0 as i32, y0 as i32);
+
+    let corner = |dx: i32, dy: i32| {
+        let hash = hash_2d(ix.wrapping_add(dx), iy.wrapping_add(dy), seed);
+        hash as f32 / u32::MAX as f32 * 2. - 1.
+    };
+
+    let (u, v) = (fade(x - x0), fade(y - y0));
+    lerp(
+        lerp(corner(0, 0), corner(1, 0), u),
+        lerp(corner(0, 1), corner(1, 1), u),
+        v,
+    )
+}
+
+/// Fractal Brownian motion: `octaves` layers of [perlin_2d], each one `lacunarity`
+/// times finer and `gain` times weaker than the previous. Normalized to roughly [-1, 1].
+///
+/// `fbm(x, y, 5, 2.0, 0.5, seed)` is a good start for terrain heightmaps.
+pub fn fbm(x: f32, y: f32, octaves: u32, lacunarity: f32, gain: f32, seed: u32) -> f32 {
+    let mut sum = 0.;
+    let mut total_amplitude = 0.;
+    let mut amplitude = 1.;
+    let mut frequency = 1.;
+
+    for octave in 0..octaves {
+        sum += perlin_2d(x * frequency, y * frequency, seed.wrapping_add(octave)) * amplitude;
+        total_amplitude += amplitude;
+        amplitude *= gain;
+        frequency *= lacunarity;
+    }
+
+    if total_amplitude > 0. {
+        sum / total_amplitude
+    } else {
+        0.
+    }
+}
+
+#[test]
+fn noise_is_deterministic_and_bounded() {
+    assert_eq!(perlin_2d(3., 7., 42), 0.);
+    assert_eq!(perlin_3d(1., 2., 3., 42), 0.);
+
+    let mut any_different = false;
+    for i in 0..200 {
+        let (x, y) = (i as f32 * 0.37, i as f32 * 0.71);
+
+        assert_eq!(perlin_2d(x, y, 1), perlin_2d(x, y, 1));
+        any_different |= perlin_2d(x, y, 1) != perlin_2d(x, y, 2);
+
+        for value in [
+            perlin_2d(x, y, 1),
+            perlin_3d(x, y, x + y, 1),
+            value_noise(x, y, 1),
+            fbm(x, y, 4, 2., 0.5, 1),
+        ] {
+            assert!((-1.0..=1.0).contains(&value), "{}", value);
+        }
+    }
+    assert!(any_different);
+}
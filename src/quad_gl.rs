@@ -4,7 +4,14 @@ use miniquad::*;
 
 pub use miniquad::{FilterMode, TextureId as MiniquadTexture, UniformDesc};
 
-use crate::{color::Color, logging::warn, telemetry, texture::Texture2D, tobytes::ToBytes, Error};
+use crate::{
+    color::Color,
+    logging::{info, warn},
+    telemetry,
+    texture::Texture2D,
+    tobytes::ToBytes,
+    Error,
+};
 
 use std::collections::BTreeMap;
 
@@ -19,6 +26,15 @@ pub enum DrawMode {
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct GlPipeline(usize);
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum StencilMode {
+    Off,
+    /// Geometry only writes 1 to the stencil buffer, colors are untouched
+    Mask,
+    /// Geometry is only drawn where the stencil buffer is 1
+    Masked,
+}
+
 struct DrawCall {
     vertices_count: usize,
     indices_count: usize,
@@ -36,6 +52,8 @@ struct DrawCall {
     uniforms: Option<Vec<u8>>,
     render_pass: Option<RenderPass>,
     capture: bool,
+    debug_group: Option<String>,
+    clear_stencil: bool,
 }
 
 impl DrawCall {
@@ -61,8 +79,52 @@ impl DrawCall {
             uniforms,
             render_pass,
             capture: false,
+            debug_group: None,
+            clear_stencil: false,
         }
     }
+
+    /// Why geometry drawn with `state` can't be appended to this draw call, if it can't.
+    fn break_reason(
+        &self,
+        state: &GlState,
+        pipeline: GlPipeline,
+        vertices: usize,
+        indices: usize,
+        max_vertices: usize,
+        max_indices: usize,
+    ) -> Option<&'static str> {
+        let reason = if self.texture != state.texture {
+            "texture changed"
+        } else if self.clip != state.clip {
+            "scissor changed"
+        } else if self.viewport != state.viewport {
+            "viewport changed"
+        } else if self.model != state.model() {
+            "model matrix changed"
+        } else if self.pipeline != pipeline {
+            "material changed"
+        } else if self.render_pass != state.render_pass {
+            "render target changed"
+        } else if self.draw_mode != state.draw_mode {
+            "draw mode changed"
+        } else if self.vertices_count >= max_vertices - vertices
+            || self.indices_count >= max_indices - indices
+        {
+            "draw call is full"
+        } else if self.capture != state.capture
+            || (state.capture && self.debug_group.as_deref() != state.debug_group())
+        {
+            "debug group changed"
+        } else if state.clear_stencil {
+            "stencil mask started"
+        } else if state.break_batching {
+            "uniforms changed"
+        } else {
+            return None;
+        };
+        Some(reason)
+    }
 }
 
 struct MagicSnapshotter {
@@ -261,18 +323,26 @@ struct GlState {
     model_stack: Vec<glam::Mat4>,
     pipeline: Option<GlPipeline>,
     depth_test_enable: bool,
+    stencil: StencilMode,
+    clear_stencil: bool,
+    log_batch_breaks: bool,
 
     break_batching: bool,
     snapshotter: MagicSnapshotter,
 
     render_pass: Option<RenderPass>,
     capture: bool,
+    debug_groups: Vec<String>,
 }
 
 impl GlState {
     fn model(&self) -> glam::Mat4 {
         *self.model_stack.last().unwrap()
     }
+
+    fn debug_group(&self) -> Option<&str> {
+        self.debug_groups.last().map(|group| group.as_str())
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -381,6 +451,10 @@ impl PipelinesStorage {
     const LINES_PIPELINE: GlPipeline = GlPipeline(1);
     const TRIANGLES_DEPTH_PIPELINE: GlPipeline = GlPipeline(2);
     const LINES_DEPTH_PIPELINE: GlPipeline = GlPipeline(3);
+    const TRIANGLES_MASK_PIPELINE: GlPipeline = GlPipeline(4);
+    const LINES_MASK_PIPELINE: GlPipeline = GlPipeline(5);
+    const TRIANGLES_MASKED_PIPELINE: GlPipeline = GlPipeline(6);
+    const LINES_MASKED_PIPELINE: GlPipeline = GlPipeline(7);
 
     fn new(ctx: &mut dyn RenderingBackend) -> PipelinesStorage {
         let shader = ctx
@@ -468,6 +542,65 @@ impl PipelinesStorage {
         );
         assert_eq!(lines_depth_pipeline, Self::LINES_DEPTH_PIPELINE);
 
+        let mask = StencilFaceState {
+            fail_op: StencilOp::Keep,
+            depth_fail_op: StencilOp::Keep,
+            pass_op: StencilOp::Replace,
+            test_func: CompareFunc::Always,
+            test_ref: 1,
+            test_mask: !0,
+            write_mask: !0,
+        };
+        let masked = StencilFaceState {
+            pass_op: StencilOp::Keep,
+            test_func: CompareFunc::Equal,
+            ..mask
+        };
+        for (primitive_type, face, color_write, expected) in [
+            (
+                PrimitiveType::Triangles,
+                mask,
+                (false, false, false, false),
+                Self::TRIANGLES_MASK_PIPELINE,
+            ),
+            (
+                PrimitiveType::Lines,
+                mask,
+                (false, false, false, false),
+                Self::LINES_MASK_PIPELINE,
+            ),
+            (
+                PrimitiveType::Triangles,
+                masked,
+                (true, true, true, true),
+                Self::TRIANGLES_MASKED_PIPELINE,
+            ),
+            (
+                PrimitiveType::Lines,
+                masked,
+                (true, true, true, true),
+                Self::LINES_MASKED_PIPELINE,
+            ),
+        ] {
+            let pipeline = storage.make_pipeline(
+                ctx,
+                shader,
+                PipelineParams {
+                    primitive_type,
+                    color_write,
+                    stencil_test: Some(StencilState {
+                        front: face,
+                        back: face,
+                    }),
+                    ..params
+                },
+                false,
+                vec![],
+                vec![],
+            );
+            assert_eq!(pipeline, expected);
+        }
+
         storage
     }
 
@@ -534,12 +667,21 @@ impl PipelinesStorage {
         GlPipeline(id)
     }
 
-    const fn get(&self, draw_mode: DrawMode, depth_enabled: bool) -> GlPipeline {
-        match (draw_mode, depth_enabled) {
-            (DrawMode::Triangles, false) => Self::TRIANGLES_PIPELINE,
-            (DrawMode::Triangles, true) => Self::TRIANGLES_DEPTH_PIPELINE,
-            (DrawMode::Lines, false) => Self::LINES_PIPELINE,
-            (DrawMode::Lines, true) => Self::LINES_DEPTH_PIPELINE,
+    const fn get(
+        &self,
+        draw_mode: DrawMode,
+        depth_enabled: bool,
+        stencil: StencilMode,
+    ) -> GlPipeline {
+        match (draw_mode, depth_enabled, stencil) {
+            (DrawMode::Triangles, _, StencilMode::Mask) => Self::TRIANGLES_MASK_PIPELINE,
+            (DrawMode::Lines, _, StencilMode::Mask) => Self::LINES_MASK_PIPELINE,
+            (DrawMode::Triangles, _, StencilMode::Masked) => Self::TRIANGLES_MASKED_PIPELINE,
+            (DrawMode::Lines, _, StencilMode::Masked) => Self::LINES_MASKED_PIPELINE,
+            (DrawMode::Triangles, false, StencilMode::Off) => Self::TRIANGLES_PIPELINE,
+            (DrawMode::Triangles, true, StencilMode::Off) => Self::TRIANGLES_DEPTH_PIPELINE,
+            (DrawMode::Lines, false, StencilMode::Off) => Self::LINES_PIPELINE,
+            (DrawMode::Lines, true, StencilMode::Off) => Self::LINES_DEPTH_PIPELINE,
         }
     }
 
@@ -567,6 +709,9 @@ pub struct QuadGl {
 
     batch_vertex_buffer: Vec<Vertex>,
     batch_index_buffer: Vec<u16>,
+
+    frame_draw_calls: usize,
+    last_frame_draw_calls: usize,
 }
 
 impl QuadGl {
@@ -588,9 +733,13 @@ impl QuadGl {
                 pipeline: None,
                 break_batching: false,
                 depth_test_enable: false,
+                stencil: StencilMode::Off,
+                clear_stencil: false,
+                log_batch_breaks: false,
                 snapshotter: MagicSnapshotter::new(ctx),
                 render_pass: None,
                 capture: false,
+                debug_groups: vec![],
             },
             draw_calls: Vec::with_capacity(200),
             draw_calls_bindings: Vec::with_capacity(200),
@@ -602,6 +751,8 @@ impl QuadGl {
             batch_index_buffer: Vec::with_capacity(max_indices),
             max_vertices,
             max_indices,
+            frame_draw_calls: 0,
+            last_frame_draw_calls: 0,
         }
     }
 
@@ -650,7 +801,21 @@ impl QuadGl {
     }
 
     pub(crate) fn clear(&mut self, ctx: &mut dyn miniquad::RenderingBackend, color: Color) {
-        let clear = PassAction::clear_color(color.r, color.g, color.b, color.a);
+        self.clear_ex(ctx, Some(color), Some(1.), None);
+    }
+
+    pub(crate) fn clear_ex(
+        &mut self,
+        ctx: &mut dyn miniquad::RenderingBackend,
+        color: Option<Color>,
+        depth: Option<f32>,
+        stencil: Option<i32>,
+    ) {
+        let clear = PassAction::Clear {
+            color: color.map(|color| (color.r, color.g, color.b, color.a)),
+            depth,
+            stencil,
+        };
 
         if let Some(current_pass) = self.state.render_pass {
             ctx.begin_pass(Some(current_pass), clear);
@@ -672,6 +837,9 @@ impl QuadGl {
         self.state.clip = None;
         self.state.texture = None;
         self.state.model_stack = vec![glam::Mat4::IDENTITY];
+        self.state.debug_groups.clear();
+        self.state.stencil = StencilMode::Off;
+        self.state.clear_stencil = false;
 
         self.draw_calls_count = 0;
     }
@@ -722,10 +890,19 @@ impl QuadGl {
                 self.state.snapshotter.snapshot(ctx, dc.render_pass);
             }
 
+            let action = if dc.clear_stencil {
+                PassAction::Clear {
+                    color: None,
+                    depth: None,
+                    stencil: Some(0),
+                }
+            } else {
+                PassAction::Nothing
+            };
             if let Some(render_pass) = dc.render_pass {
-                ctx.begin_pass(Some(render_pass), PassAction::Nothing);
+                ctx.begin_pass(Some(render_pass), action);
             } else {
-                ctx.begin_default_pass(PassAction::Nothing);
+                ctx.begin_default_pass(action);
             }
 
             ctx.buffer_update(
@@ -788,7 +965,12 @@ impl QuadGl {
             ctx.end_render_pass();
 
             if dc.capture {
-                telemetry::track_drawcall(&pipeline.pipeline, bindings, dc.indices_count);
+                telemetry::track_drawcall(
+                    &pipeline.pipeline,
+                    bindings,
+                    dc.indices_count,
+                    dc.debug_group.take(),
+                );
             }
 
             dc.vertices_count = 0;
@@ -797,11 +979,27 @@ impl QuadGl {
             dc.indices_start = 0;
         }
 
+        self.frame_draw_calls += self.draw_calls_count;
         self.draw_calls_count = 0;
         self.batch_index_buffer.clear();
         self.batch_vertex_buffer.clear();
     }
 
+    pub(crate) fn end_frame(&mut self) {
+        self.last_frame_draw_calls = self.frame_draw_calls;
+        self.frame_draw_calls = 0;
+    }
+
+    /// Draw calls issued during the last frame.
+    pub const fn last_frame_draw_calls(&self) -> usize {
+        self.last_frame_draw_calls
+    }
+
+    /// Log the reason every time a new draw call has to be started.
+    pub fn log_batch_breaks(&mut self, enabled: bool) {
+        self.state.log_batch_breaks = enabled;
+    }
+
     pub(crate) fn capture(&mut self, capture: bool) {
         self.state.capture = capture;
     }
@@ -822,6 +1020,10 @@ impl QuadGl {
         self.state.depth_test_enable
     }
 
+    pub(crate) const fn get_active_pipeline(&self) -> Option<GlPipeline> {
+        self.state.pipeline
+    }
+
     pub fn render_pass(&mut self, render_pass: Option<RenderPass>) {
         self.state.render_pass = render_pass;
     }
@@ -830,6 +1032,26 @@ impl QuadGl {
         self.state.depth_test_enable = enable;
     }
 
+    /// Following geometry is not drawn, but marks where [QuadGl::begin_stencil_masked]
+    /// content will be visible. The mask starts empty.
+    ///
+    /// Only the built-in pipelines honor the mask, geometry drawn with a custom
+    /// material ignores it. The screen needs a stencil buffer, which is not
+    /// the case for render targets and WebGL canvases.
+    pub fn begin_stencil_mask(&mut self) {
+        self.state.stencil = StencilMode::Mask;
+        self.state.clear_stencil = true;
+    }
+
+    /// Following geometry is only drawn where the mask is.
+    pub fn begin_stencil_masked(&mut self) {
+        self.state.stencil = StencilMode::Masked;
+    }
+
+    pub fn end_stencil(&mut self) {
+        self.state.stencil = StencilMode::Off;
+    }
+
     pub fn texture(&mut self, texture: Option<&Texture2D>) {
         let ctx = crate::get_context();
         self.state.texture = texture.map(|t| ctx.raw_miniquad_id(&t.texture));
@@ -852,6 +1074,11 @@ impl QuadGl {
         ))
     }
 
+    /// The viewport set with [QuadGl::viewport], none means the whole render target.
+    pub(crate) const fn get_viewport_override(&self) -> Option<(i32, i32, i32, i32)> {
+        self.state.viewport
+    }
+
     pub fn push_model_matrix(&mut self, matrix: glam::Mat4) {
         self.state.model_stack.push(self.state.model() * matrix);
     }
@@ -862,6 +1089,16 @@ impl QuadGl {
         }
     }
 
+    /// Label all following draw calls with `name` in the captured frame telemetry,
+    /// until the matching `pop_debug_group`. Groups may be nested.
+    pub fn push_debug_group(&mut self, name: &str) {
+        self.state.debug_groups.push(name.to_string());
+    }
+
+    pub fn pop_debug_group(&mut self) {
+        self.state.debug_groups.pop();
+    }
+
     pub fn pipeline(&mut self, pipeline: Option<GlPipeline>) {
         if self.state.pipeline == pipeline {
             return;
@@ -875,6 +1112,11 @@ impl QuadGl {
         self.state.draw_mode = mode;
     }
 
+    /// Vertices and indices a single `geometry` call has to stay below to not get clamped.
+    pub(crate) const fn draw_call_capacity(&self) -> (usize, usize) {
+        (self.max_vertices, self.max_indices)
+    }
+
     pub fn geometry(&mut self, vertices: &[Vertex], indices: &[u16]) {
         if vertices.len() >= self.max_vertices || indices.len() >= self.max_indices {
             warn!("geometry() exceeded max drawcall size, clamping");
@@ -883,10 +1125,11 @@ impl QuadGl {
         let vertices = &vertices[0..self.max_vertices.min(vertices.len())];
         let indices = &indices[0..self.max_indices.min(indices.len())];
 
-        let pip = self.state.pipeline.unwrap_or(
-            self.pipelines
-                .get(self.state.draw_mode, self.state.depth_test_enable),
-        );
+        let pip = self.state.pipeline.unwrap_or(self.pipelines.get(
+            self.state.draw_mode,
+            self.state.depth_test_enable,
+            self.state.stencil,
+        ));
 
         let previous_dc_ix = if self.draw_calls_count == 0 {
             None
@@ -895,19 +1138,25 @@ impl QuadGl {
         };
         let previous_dc = previous_dc_ix.and_then(|ix| self.draw_calls.get(ix));
 
-        if previous_dc.map_or(true, |draw_call| {
-            draw_call.texture != self.state.texture
-                || draw_call.clip != self.state.clip
-                || draw_call.viewport != self.state.viewport
-                || draw_call.model != self.state.model()
-                || draw_call.pipeline != pip
-                || draw_call.render_pass != self.state.render_pass
-                || draw_call.draw_mode != self.state.draw_mode
-                || draw_call.vertices_count >= self.max_vertices - vertices.len()
-                || draw_call.indices_count >= self.max_indices - indices.len()
-                || draw_call.capture != self.state.capture
-                || self.state.break_batching
-        }) {
+        let break_reason = match previous_dc {
+            None => Some("first draw call"),
+            Some(draw_call) => {
+                let reason = draw_call.break_reason(
+                    &self.state,
+                    pip,
+                    vertices.len(),
+                    indices.len(),
+                    self.max_vertices,
+                    self.max_indices,
+                );
+                if let (Some(reason), true) = (reason, self.state.log_batch_breaks) {
+                    info!("new draw call: {}", reason);
+                }
+                reason
+            }
+        };
+
+        if break_reason.is_some() {
             let uniforms = self.state.pipeline.map_or(None, |pipeline| {
                 Some(
                     self.pipelines
@@ -937,11 +1186,19 @@ impl QuadGl {
             self.draw_calls[self.draw_calls_count].pipeline = pip;
             self.draw_calls[self.draw_calls_count].render_pass = self.state.render_pass;
             self.draw_calls[self.draw_calls_count].capture = self.state.capture;
+            self.draw_calls[self.draw_calls_count].clear_stencil = self.state.clear_stencil;
+            // labels are only needed for telemetry, so don't allocate them on regular frames
+            self.draw_calls[self.draw_calls_count].debug_group = if self.state.capture {
+                self.state.debug_group().map(|group| group.to_string())
+            } else {
+                None
+            };
             self.draw_calls[self.draw_calls_count].indices_start = self.batch_index_buffer.len();
             self.draw_calls[self.draw_calls_count].vertices_start = self.batch_vertex_buffer.len();
 
             self.draw_calls_count += 1;
             self.state.break_batching = false;
+            self.state.clear_stencil = false;
         };
         let dc = &mut self.draw_calls[self.draw_calls_count - 1];
 
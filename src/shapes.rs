@@ -22,6 +22,24 @@ pub fn draw_triangle(v1: Vec2, v2: Vec2, v3: Vec2, color: Color) {
     context.gl.geometry(&vertices, &indices);
 }
 
+/// Draws a solid triangle between points `v1`, `v2`, and `v3`, each vertex with its own color.
+/// Colors are interpolated across the triangle.
+pub fn draw_triangle_gradient(v1: Vec2, v2: Vec2, v3: Vec2, c1: Color, c2: Color, c3: Color) {
+    let context = get_context();
+
+    let vertices = [
+        Vertex::new(v1.x, v1.y, 0., 0., 0., c1),
+        Vertex::new(v2.x, v2.y, 0., 0., 0., c2),
+        Vertex::new(v3.x, v3.y, 0., 0., 0., c3),
+    ];
+
+    let indices: [u16; 3] = [0, 1, 2];
+
+    context.gl.texture(None);
+    context.gl.draw_mode(DrawMode::Triangles);
+    context.gl.geometry(&vertices, &indices);
+}
+
 /// Draws a triangle outline between points `v1`, `v2`, and `v3` with a given line `thickness` and `color`.
 pub fn draw_triangle_lines(v1: Vec2, v2: Vec2, v3: Vec2, thickness: f32, color: Color) {
     draw_line(v1.x, v1.y, v2.x, v2.y, thickness, color);
@@ -48,6 +66,35 @@ pub fn draw_rectangle(x: f32, y: f32, w: f32, h: f32, color: Color) {
     context.gl.geometry(&vertices, &indices);
 }
 
+/// Draws a solid rectangle with its top-left corner at `[x, y]` with size `[w, h]`,
+/// with a color for each corner interpolated across the rectangle.
+#[allow(clippy::too_many_arguments)]
+pub fn draw_rectangle_gradient(
+    x: f32,
+    y: f32,
+    w: f32,
+    h: f32,
+    top_left: Color,
+    top_right: Color,
+    bottom_left: Color,
+    bottom_right: Color,
+) {
+    let context = get_context();
+
+    #[rustfmt::skip]
+    let vertices = [
+        Vertex::new(x    , y    , 0., 0.0, 0.0, top_left),
+        Vertex::new(x + w, y    , 0., 1.0, 0.0, top_right),
+        Vertex::new(x + w, y + h, 0., 1.0, 1.0, bottom_right),
+        Vertex::new(x    , y + h, 0., 0.0, 1.0, bottom_left),
+    ];
+    let indices: [u16; 6] = [0, 1, 2, 0, 2, 3];
+
+    context.gl.texture(None);
+    context.gl.draw_mode(DrawMode::Triangles);
+    context.gl.geometry(&vertices, &indices);
+}
+
 /// Draws a rectangle outline with its top-left corner at `[x, y]` with size `[w, h]` (width going to
 /// the right, height going down), with a given line `thickness` and `color`.
 pub fn draw_rectangle_lines(x: f32, y: f32, w: f32, h: f32, thickness: f32, color: Color) {
@@ -75,6 +122,74 @@ pub fn draw_rectangle_lines(x: f32, y: f32, w: f32, h: f32, thickness: f32, colo
     context.gl.geometry(&vertices, &indices);
 }
 
+/// Draws a solid rectangle with rounded corners, with its top-left corner at `[x, y]`
+/// with size `[w, h]`, corner `radius` and `color`.
+///
+/// `radius` is clamped to half of the smaller side, so big values give a capsule or a circle.
+pub fn draw_rectangle_rounded(x: f32, y: f32, w: f32, h: f32, radius: f32, color: Color) {
+    let points = rounded_rectangle_points(x, y, w, h, radius);
+
+    let mut vertices = Vec::<Vertex>::with_capacity(points.len() + 1);
+    let mut indices = Vec::<u16>::with_capacity(points.len() * 3);
+
+    // the shape is convex, so a fan from the center covers it
+    vertices.push(Vertex::new(x + w / 2., y + h / 2., 0., 0., 0., color));
+    for (i, point) in points.iter().enumerate() {
+        vertices.push(Vertex::new(point.x, point.y, 0., 0., 0., color));
+        let next = (i + 1) % points.len();
+        indices.extend([0, i as u16 + 1, next as u16 + 1]);
+    }
+
+    let context = get_context();
+    context.gl.texture(None);
+    context.gl.draw_mode(DrawMode::Triangles);
+    context.gl.geometry(&vertices, &indices);
+}
+
+/// Draws a rounded rectangle outline with its top-left corner at `[x, y]` with size `[w, h]`,
+/// corner `radius`, line `thickness` and `color`. The line is drawn inside the rectangle.
+pub fn draw_rectangle_rounded_lines(
+    x: f32,
+    y: f32,
+    w: f32,
+    h: f32,
+    radius: f32,
+    thickness: f32,
+    color: Color,
+) {
+    let t = thickness / 2.;
+    let points = rounded_rectangle_points(x + t, y + t, w - thickness, h - thickness, radius - t);
+
+    draw_polygon_lines(&points, thickness, color);
+}
+
+fn rounded_rectangle_points(x: f32, y: f32, w: f32, h: f32, radius: f32) -> Vec<Vec2> {
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    let w = w.max(0.);
+    let h = h.max(0.);
+    let radius = radius.clamp(0., w.min(h) / 2.);
+    // a segment per ~4 units of the corner arc
+    let segments = (radius * FRAC_PI_2 / 4.).ceil().clamp(2., 64.) as usize;
+
+    let corners = [
+        (vec2(x + w - radius, y + radius), -FRAC_PI_2),
+        (vec2(x + w - radius, y + h - radius), 0.),
+        (vec2(x + radius, y + h - radius), FRAC_PI_2),
+        (vec2(x + radius, y + radius), PI),
+    ];
+
+    let mut points = Vec::with_capacity(corners.len() * (segments + 1));
+    for (center, start) in corners {
+        for i in 0..=segments {
+            let angle = start + i as f32 / segments as f32 * FRAC_PI_2;
+            points.push(center + Vec2::from_angle(angle) * radius);
+        }
+    }
+
+    points
+}
+
 pub fn draw_rectangle_lines_ex(
     x: f32,
     y: f32,
@@ -258,6 +373,150 @@ pub fn draw_circle_lines(x: f32, y: f32, r: f32, thickness: f32, color: Color) {
     draw_poly_lines(x, y, 30, r, 0., thickness, color);
 }
 
+/// Draws a solid circle with a smooth, anti-aliased edge, centered at `[x, y]`
+/// with a given radius `r` and `color`.
+///
+/// The edge fades out over one unit, so it looks best with a camera where
+/// one unit is one pixel, like the default one.
+/// The number of segments grows with the radius, so big circles stay round.
+pub fn draw_circle_aa(x: f32, y: f32, r: f32, color: Color) {
+    let transparent = Color { a: 0., ..color };
+
+    draw_rings(
+        x,
+        y,
+        Some(color),
+        &[(r - 0.5, color), (r + 0.5, transparent)],
+    );
+}
+
+/// Draws an anti-aliased circle outline centered at `[x, y]` with a given radius,
+/// line `thickness` and `color`.
+///
+/// Same as in `draw_circle_lines`, the line goes outwards from `r`.
+pub fn draw_circle_lines_aa(x: f32, y: f32, r: f32, thickness: f32, color: Color) {
+    let transparent = Color { a: 0., ..color };
+
+    if thickness < 1. {
+        // too thin to have a solid middle, fade it proportionally instead
+        let middle = r + thickness / 2.;
+        let color = Color {
+            a: color.a * thickness,
+            ..color
+        };
+        draw_rings(
+            x,
+            y,
+            None,
+            &[
+                (middle - 1., transparent),
+                (middle, color),
+                (middle + 1., transparent),
+            ],
+        );
+    } else {
+        draw_rings(
+            x,
+            y,
+            None,
+            &[
+                (r - 0.5, transparent),
+                (r + 0.5, color),
+                (r + thickness - 0.5, color),
+                (r + thickness + 0.5, transparent),
+            ],
+        );
+    }
+}
+
+/// Concentric rings with per-ring colors, blended between neighbour rings.
+/// With a `center` color the first ring is filled as well.
+fn draw_rings(x: f32, y: f32, center: Option<Color>, rings: &[(f32, Color)]) {
+    let context = get_context();
+    let (max_vertices, max_indices) = context.gl.draw_call_capacity();
+    let (vertices, indices) = ring_geometry(x, y, center, rings, max_vertices, max_indices);
+
+    context.gl.texture(None);
+    context.gl.draw_mode(DrawMode::Triangles);
+    context.gl.geometry(&vertices, &indices);
+}
+
+/// Mesh for `draw_rings`, with as few sides as it takes to fit in one draw call.
+fn ring_geometry(
+    x: f32,
+    y: f32,
+    center: Option<Color>,
+    rings: &[(f32, Color)],
+    max_vertices: usize,
+    max_indices: usize,
+) -> (Vec<Vertex>, Vec<u16>) {
+    let ring_count = rings.len();
+    let indices_per_side = 6 * (ring_count - 1) + if center.is_some() { 3 } else { 0 };
+
+    let outer = rings.last().map_or(0., |ring| ring.0);
+    // a segment per ~4 units of circumference, as long as it fits in one draw call
+    let max_sides = ((max_indices - 1) / indices_per_side).min((max_vertices - 2) / ring_count);
+    let sides = ((outer * std::f32::consts::TAU / 4.).ceil().clamp(16., 512.) as usize)
+        .min(max_sides)
+        .max(3);
+
+    let mut vertices = Vec::<Vertex>::with_capacity(sides * ring_count + 1);
+    let mut indices = Vec::<u16>::with_capacity(sides * indices_per_side);
+
+    for i in 0..sides {
+        let dir = Vec2::from_angle(i as f32 / sides as f32 * std::f32::consts::TAU);
+        for &(radius, color) in rings {
+            let point = vec2(x, y) + dir * radius.max(0.);
+            vertices.push(Vertex::new(point.x, point.y, 0., 0., 0., color));
+        }
+    }
+
+    for i in 0..sides {
+        let this = i * ring_count;
+        let next = (i + 1) % sides * ring_count;
+        for ring in 0..ring_count - 1 {
+            indices.extend(
+                [this, this + 1, next, next, this + 1, next + 1].map(|k| (k + ring) as u16),
+            );
+        }
+    }
+
+    if let Some(color) = center {
+        let center_ix = vertices.len();
+        vertices.push(Vertex::new(x, y, 0., 0., 0., color));
+        for i in 0..sides {
+            let next = (i + 1) % sides;
+            indices.extend([center_ix, i * ring_count, next * ring_count].map(|k| k as u16));
+        }
+    }
+
+    (vertices, indices)
+}
+
+#[test]
+fn big_rings_fit_in_one_draw_call() {
+    use crate::color::WHITE;
+
+    let rings = [
+        (999.5, WHITE),
+        (1000.5, WHITE),
+        (1010.5, WHITE),
+        (1011.5, WHITE),
+    ];
+    for center in [None, Some(WHITE)] {
+        let (vertices, indices) = ring_geometry(0., 0., center, &rings, 10000, 5000);
+        assert!(vertices.len() < 10000);
+        assert!(indices.len() < 5000);
+        // still a lot of sides, the ring stays round
+        assert!(vertices.len() / rings.len() > 200);
+    }
+
+    // small circles are not affected
+    let small = [(9.5, WHITE), (10.5, WHITE)];
+    let (_, indices) = ring_geometry(0., 0., Some(WHITE), &small, 10000, 5000);
+    assert_eq!(indices.len(), 17 * 9);
+}
+
 /// Draws a solid ellipse centered at `[x, y]` with a given size `[w, h]`,
 /// clockwise `rotation` (in degrees) and `color`.
 pub fn draw_ellipse(x: f32, y: f32, w: f32, h: f32, rotation: f32, color: Color) {
@@ -363,6 +622,78 @@ pub fn draw_line(x1: f32, y1: f32, x2: f32, y2: f32, thickness: f32, color: Colo
     );
 }
 
+/// Draws a line strip through `points`, with a given line `thickness` and `color`.
+///
+/// Unlike a sequence of `draw_line` calls, segments are joined with mitered corners
+/// and the whole strip is a single geometry batch.
+pub fn draw_polyline(points: &[Vec2], thickness: f32, color: Color) {
+    draw_polyline_ex(points, false, thickness, color);
+}
+
+/// Draws a closed polygon outline through `points`, with a given line `thickness` and `color`.
+pub fn draw_polygon_lines(points: &[Vec2], thickness: f32, color: Color) {
+    draw_polyline_ex(points, true, thickness, color);
+}
+
+fn draw_polyline_ex(points: &[Vec2], closed: bool, thickness: f32, color: Color) {
+    // zero length segments have no direction to build the normal from
+    let mut path = Vec::<Vec2>::with_capacity(points.len());
+    for &point in points {
+        match path.last() {
+            Some(last) if last.distance(point) <= f32::EPSILON => {}
+            _ => path.push(point),
+        }
+    }
+    if closed && path.len() > 2 && path[0].distance(path[path.len() - 1]) <= f32::EPSILON {
+        path.pop();
+    }
+    if path.len() < 2 {
+        return;
+    }
+
+    let n = path.len();
+    let segments = if closed { n } else { n - 1 };
+    let normal = |i: usize| (path[(i + 1) % n] - path[i]).normalize().perp();
+    let half = thickness / 2.;
+
+    let mut vertices = Vec::<Vertex>::with_capacity(n * 2);
+    let mut indices = Vec::<u16>::with_capacity(segments * 6);
+
+    for (i, point) in path.iter().enumerate() {
+        let (offset, scale) = match (i, closed) {
+            (0, false) => (normal(0), half),
+            (i, false) if i == n - 1 => (normal(n - 2), half),
+            (i, _) => {
+                let prev = normal((i + n - 1) % n);
+                let next = normal(i);
+                let miter = (prev + next).normalize_or_zero();
+                // limit miter length on very sharp corners, like svg's default miter limit
+                let cos = miter.dot(next).max(0.25);
+                if miter == Vec2::ZERO {
+                    (next, half)
+                } else {
+                    (miter, half / cos)
+                }
+            }
+        };
+        let a = *point + offset * scale;
+        let b = *point - offset * scale;
+        vertices.push(Vertex::new(a.x, a.y, 0., 0., 0., color));
+        vertices.push(Vertex::new(b.x, b.y, 0., 0., 0., color));
+    }
+
+    for i in 0..segments {
+        let j = (i + 1) % n;
+        let (a0, b0, a1, b1) = (i * 2, i * 2 + 1, j * 2, j * 2 + 1);
+        indices.extend([a0, b0, a1, a1, b0, b1].map(|k| k as u16));
+    }
+
+    let context = get_context();
+    context.gl.texture(None);
+    context.gl.draw_mode(DrawMode::Triangles);
+    context.gl.geometry(&vertices, &indices);
+}
+
 /// Draw arc from `rotation`(in degrees) to `arc + rotation` (`arc` in degrees),
 /// centered at `[x, y]` with a given number of `sides`, `radius`, line `thickness`, and `color`.
 pub fn draw_arc(
@@ -1,6 +1,13 @@
-use crate::{get_context, get_quad_context, time::get_time};
+use crate::{
+    color::{Color, WHITE},
+    get_context, get_quad_context,
+    math::Rect,
+    shapes::{draw_line, draw_rectangle},
+    text::draw_text,
+    time::{get_frame_time, get_time},
+};
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 
 static mut PROFILER: Option<Profiler> = None;
 
@@ -92,6 +99,34 @@ pub fn end_gpu_query() {
     get_profiler().end_gpu_query();
 }
 
+/// Measure how long the GPU spent on the draw calls made inside `f`.
+///
+/// Batched geometry is flushed before and after `f`, so the measurement
+/// covers exactly the draw calls made by `f`.
+/// The zone is measured on its first call and then each time `sample_gpu_queries` is called,
+/// the result shows up in `gpu_queries` a couple of frames later, as GPU works asynchronously.
+///
+/// Only one timer query may run at a time, so while the built-in "GPU" frame query
+/// is being sampled `f` is called without measurement.
+/// ```ignore
+/// telemetry::gpu_zone("shadows", || {
+///     draw_shadows();
+/// });
+/// ```
+pub fn gpu_zone<T, F: FnOnce() -> T>(name: &str, f: F) -> T {
+    if get_profiler().query_in_progress() {
+        return f();
+    }
+
+    get_context().perform_render_passes();
+    get_profiler().begin_zone_query(name);
+    let res = f();
+    get_context().perform_render_passes();
+    get_profiler().end_zone_query(name);
+
+    res
+}
+
 /// Workaround to stop gl capture on debug rendering
 #[doc(hidden)]
 pub fn pause_gl_capture() {
@@ -168,16 +203,63 @@ impl Frame {
             active_zone: std::ptr::null_mut(),
         })
     }
+
+    /// Serialize the zones tree into Chrome tracing JSON,
+    /// readable by chrome://tracing or https://ui.perfetto.dev
+    pub fn to_chrome_trace(&self) -> String {
+        fn write_zone(zone: &Zone, events: &mut Vec<String>) {
+            let mut name = String::new();
+            for c in zone.name.chars() {
+                match c {
+                    '"' => name.push_str("\\\""),
+                    '\\' => name.push_str("\\\\"),
+                    c if (c as u32) < 0x20 => name.push_str(&format!("\\u{:04x}", c as u32)),
+                    c => name.push(c),
+                }
+            }
+
+            // chrome tracing timestamps are in microseconds
+            events.push(format!(
+                r#"{{"name":"{}","ph":"X","ts":{:.3},"dur":{:.3},"pid":0,"tid":0}}"#,
+                name,
+                zone.start_time * 1_000_000.,
+                zone.duration * 1_000_000.
+            ));
+            for child in &zone.children {
+                write_zone(child, events);
+            }
+        }
+
+        let mut events = vec![];
+        for zone in &self.zones {
+            write_zone(zone, &mut events);
+        }
+
+        format!(r#"{{"traceEvents":[{}]}}"#, events.join(","))
+    }
 }
 
 pub fn frame() -> Frame {
     get_profiler().prev_frame.clone()
 }
 
+/// Write the zones of the last finished frame into a Chrome tracing JSON file.
+///
+/// Zones are only recorded while telemetry is enabled, see `enable`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn export_chrome_trace(path: &str) -> std::io::Result<()> {
+    std::fs::write(path, frame().to_chrome_trace())
+}
+
+/// Last measured (name, nanoseconds) of each GPU query.
+///
+/// Queries without a result yet are skipped, so on backends without timer queries
+/// support this is always empty.
 pub fn gpu_queries() -> Vec<(String, u64)> {
     get_profiler()
         .queries
         .iter()
+        .filter(|(_, query)| query.value != 0)
         .map(|(name, query)| (name.to_owned(), query.value))
         .collect()
 }
@@ -241,6 +323,38 @@ impl Profiler {
         }
     }
 
+    fn query_in_progress(&self) -> bool {
+        self.queries.values().any(|query| query.in_progress)
+    }
+
+    fn begin_zone_query(&mut self, name: &str) {
+        let query = self
+            .queries
+            .entry(name.to_string())
+            .or_insert_with(|| GpuQuery {
+                query: miniquad::graphics::ElapsedQuery::new(),
+                in_progress: false,
+                value: 0,
+                force_resume: true,
+            });
+        if query.force_resume {
+            query.in_progress = true;
+            query.query.begin_query();
+        }
+    }
+
+    fn end_zone_query(&mut self, name: &str) {
+        let query = self.queries.get_mut(name).unwrap();
+        if query.in_progress {
+            query.force_resume = false;
+            query.in_progress = false;
+            query.query.end_query();
+        }
+        if query.query.is_available() {
+            query.value = query.query.get_result();
+        }
+    }
+
     fn begin_zone(&mut self, name: &str) {
         let zones = if self.frame.active_zone.is_null() {
             &mut self.frame.zones
@@ -279,6 +393,95 @@ pub struct GpuQuery {
     pub force_resume: bool,
 }
 
+/// Tiny frame time graph, for an always-on overlay without the whole profiler window.
+///
+/// ```ignore
+/// let mut graph = FrameTimeGraph::new(100);
+/// loop {
+///     graph.update();
+///     // ...
+///     graph.draw(Rect::new(10., 10., 200., 60.));
+///     next_frame().await;
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct FrameTimeGraph {
+    pub color: Color,
+    pub background: Color,
+    samples: VecDeque<f32>,
+    capacity: usize,
+}
+
+impl FrameTimeGraph {
+    /// Graph keeping the last `capacity` samples.
+    pub fn new(capacity: usize) -> FrameTimeGraph {
+        FrameTimeGraph {
+            color: Color::new(0.6, 0.6, 1.0, 1.0),
+            background: Color::new(0.0, 0.0, 0.0, 0.5),
+            samples: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Add this frame's `get_frame_time()`.
+    pub fn update(&mut self) {
+        self.push(get_frame_time());
+    }
+
+    /// Add a sample, in seconds. The oldest sample is dropped when the graph is full.
+    pub fn push(&mut self, sample: f32) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    /// (min, max, average) of the samples, in seconds.
+    pub fn stats(&self) -> (f32, f32, f32) {
+        if self.samples.is_empty() {
+            return (0., 0., 0.);
+        }
+
+        let min = self.samples.iter().copied().fold(f32::MAX, f32::min);
+        let max = self.samples.iter().copied().fold(0., f32::max);
+        let avg = self.samples.iter().sum::<f32>() / self.samples.len() as f32;
+
+        (min, max, avg)
+    }
+
+    /// Draw the graph inside `rect` with the current camera,
+    /// one vertical line per sample, scaled so the slowest sample fills the rect.
+    pub fn draw(&self, rect: Rect) {
+        let (min, max, avg) = self.stats();
+
+        draw_rectangle(rect.x, rect.y, rect.w, rect.h, self.background);
+
+        let step = rect.w / self.capacity as f32;
+        let scale = if max > 0. { rect.h / max } else { 0. };
+        let bottom = rect.y + rect.h;
+        for (n, sample) in self.samples.iter().enumerate() {
+            let x = rect.x + (n as f32 + 0.5) * step;
+            draw_line(x, bottom, x, bottom - sample * scale, step, self.color);
+        }
+
+        let avg_y = bottom - avg * scale;
+        draw_line(rect.x, avg_y, rect.x + rect.w, avg_y, 1., WHITE);
+
+        draw_text(
+            &format!(
+                "min {:.1}ms avg {:.1}ms max {:.1}ms",
+                min * 1000.,
+                avg * 1000.,
+                max * 1000.
+            ),
+            rect.x + 2.,
+            rect.y + 14.,
+            16.,
+            WHITE,
+        );
+    }
+}
+
 pub fn scene_allocated_memory() -> usize {
     use crate::experimental::scene;
 
@@ -342,16 +545,31 @@ pub fn capture_frame() {
     get_profiler().capture_request = true;
 }
 
+/// Draw calls issued during the last frame.
+pub fn draw_call_count() -> usize {
+    get_context().gl.last_frame_draw_calls()
+}
+
+/// Log why each new draw call had to be started instead of being batched
+/// with the previous one: texture changed, model matrix changed...
+pub fn set_batch_break_logging(enabled: bool) {
+    get_context().gl.log_batch_breaks(enabled);
+}
+
 #[derive(Clone, Debug)]
 pub struct DrawCallTelemetry {
     pub indices_count: usize,
     pub texture: miniquad::TextureId,
+    /// Innermost debug group active when the draw call was recorded,
+    /// see `QuadGl::push_debug_group`.
+    pub debug_group: Option<String>,
 }
 
 pub(crate) fn track_drawcall(
     pipeline: &miniquad::Pipeline,
     bindings: &miniquad::Bindings,
     indices_count: usize,
+    debug_group: Option<String>,
 ) {
     let texture = get_quad_context().new_render_texture(miniquad::TextureParams {
         width: 128,
@@ -369,9 +587,52 @@ pub(crate) fn track_drawcall(
     get_profiler().drawcalls.push(DrawCallTelemetry {
         indices_count,
         texture,
+        debug_group,
     });
 }
 
+#[test]
+fn chrome_trace_contains_nested_zones() {
+    let frame = Frame {
+        full_frame_time: 0.016,
+        zones: vec![Zone {
+            name: "update".to_string(),
+            start_time: 1.0,
+            duration: 0.5,
+            children: vec![Zone {
+                name: "\"physics\"".to_string(),
+                start_time: 1.25,
+                duration: 0.125,
+                children: vec![],
+                parent: std::ptr::null_mut(),
+            }],
+            parent: std::ptr::null_mut(),
+        }],
+        active_zone: std::ptr::null_mut(),
+    };
+
+    assert_eq!(
+        frame.to_chrome_trace(),
+        concat!(
+            r#"{"traceEvents":["#,
+            r#"{"name":"update","ph":"X","ts":1000000.000,"dur":500000.000,"pid":0,"tid":0},"#,
+            r#"{"name":"\"physics\"","ph":"X","ts":1250000.000,"dur":125000.000,"pid":0,"tid":0}"#,
+            "]}"
+        )
+    );
+}
+
+#[test]
+fn frame_time_graph_keeps_last_samples() {
+    let mut graph = FrameTimeGraph::new(3);
+    assert_eq!(graph.stats(), (0., 0., 0.));
+
+    for sample in [4., 1., 2., 3.] {
+        graph.push(sample);
+    }
+    assert_eq!(graph.stats(), (1., 3., 2.));
+}
+
 pub fn textures_count() -> usize {
     get_context().textures.len()
 }
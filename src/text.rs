@@ -5,16 +5,19 @@ use std::collections::HashMap;
 use crate::{
     color::Color,
     get_context, get_quad_context,
+    material::Material,
     math::{vec3, Rect},
     texture::{Image, TextureHandle},
     Error,
 };
 
 use crate::color::WHITE;
-use glam::vec2;
+use crate::quad_gl::{DrawMode, Vertex};
+use glam::{vec2, Vec2};
 
 use std::sync::{Arc, Mutex};
 pub(crate) mod atlas;
+mod sdf;
 
 use atlas::{Atlas, SpriteKey};
 
@@ -32,6 +35,13 @@ pub struct Font {
     font: Arc<fontdue::Font>,
     atlas: Arc<Mutex<Atlas>>,
     characters: Arc<Mutex<HashMap<(char, u16), CharacterInfo>>>,
+    sdf: Arc<Mutex<Option<SdfGlyphs>>>,
+}
+
+/// Distance field glyphs of a font, one size for all the font sizes.
+struct SdfGlyphs {
+    atlas: Atlas,
+    characters: HashMap<char, CharacterInfo>,
 }
 
 /// World space dimensions of the text, measured by "measure_text" function
@@ -70,6 +80,7 @@ impl Font {
             )?),
             characters: Arc::new(Mutex::new(HashMap::new())),
             atlas,
+            sdf: Arc::new(Mutex::new(None)),
         })
     }
 
@@ -106,23 +117,39 @@ impl Font {
             panic!("Vertical fonts are not supported");
         }
 
-        let (width, height) = (metrics.width as u16, metrics.height as u16);
-
-        let sprite = self.atlas.lock().unwrap().new_unique_id();
-        self.atlas.lock().unwrap().cache_sprite(
-            sprite,
-            Image {
+        let missing = self.font.lookup_glyph_index(character) == 0
+            && (metrics.width == 0 || metrics.height == 0)
+            && !character.is_whitespace()
+            && !character.is_control();
+
+        let (image, advance, offset_x, offset_y) = if missing {
+            // the font has no glyph and no visible ".notdef" either,
+            // draw a box so missing characters are noticeable
+            let width = (size as f32 * 0.5).round().max(3.) as u16;
+            let height = (self.ascent(size as f32) * 0.8).round().max(3.) as u16;
+            let margin = (size as f32 * 0.05).round().max(1.);
+
+            (
+                fallback_glyph_image(width, height),
+                width as f32 + margin * 2.,
+                margin as i32,
+                0,
+            )
+        } else {
+            let image = Image {
                 bytes: bitmap
                     .iter()
                     .flat_map(|coverage| vec![255, 255, 255, *coverage])
                     .collect(),
-                width,
-                height,
-            },
-        );
-        let advance = metrics.advance_width;
+                width: metrics.width as u16,
+                height: metrics.height as u16,
+            };
 
-        let (offset_x, offset_y) = (metrics.xmin, metrics.ymin);
+            (image, metrics.advance_width, metrics.xmin, metrics.ymin)
+        };
+
+        let sprite = self.atlas.lock().unwrap().new_unique_id();
+        self.atlas.lock().unwrap().cache_sprite(sprite, image);
 
         let character_info = CharacterInfo {
             advance,
@@ -137,6 +164,29 @@ impl Font {
             .insert((character, size), character_info);
     }
 
+    fn cache_sdf_glyph(&self, character: char, sdf: &mut SdfGlyphs) {
+        if sdf.characters.contains_key(&character) {
+            return;
+        }
+
+        let (metrics, bitmap) = self.font.rasterize(character, sdf::SDF_FONT_SIZE);
+        let image = sdf::coverage_to_sdf(&bitmap, metrics.width, metrics.height, sdf::SPREAD);
+
+        let sprite = sdf.atlas.new_unique_id();
+        sdf.atlas.cache_sprite(sprite, image);
+
+        let spread = sdf::SPREAD as i32;
+        sdf.characters.insert(
+            character,
+            CharacterInfo {
+                advance: metrics.advance_width,
+                offset_x: metrics.xmin - spread,
+                offset_y: metrics.ymin - spread,
+                sprite,
+            },
+        );
+    }
+
     pub(crate) fn get(&self, character: char, size: u16) -> Option<CharacterInfo> {
         self.characters
             .lock()
@@ -159,6 +209,10 @@ impl Font {
         font_scale_x: f32,
         font_scale_y: f32,
     ) -> TextDimensions {
+        if text.is_empty() {
+            return TextDimensions::default();
+        }
+
         let dpi_scaling = miniquad::window::dpi_scale();
         let font_size = (font_size as f32 * dpi_scaling).ceil() as u16;
 
@@ -189,6 +243,20 @@ impl Font {
     }
 }
 
+/// Outlined rectangle, drawn in place of characters missing in the font.
+fn fallback_glyph_image(width: u16, height: u16) -> Image {
+    let mut image = Image::gen_image_color(width, height, Color::new(1.0, 1.0, 1.0, 0.0));
+    for y in 0..height as u32 {
+        for x in 0..width as u32 {
+            if x == 0 || y == 0 || x == width as u32 - 1 || y == height as u32 - 1 {
+                image.set_pixel(x, y, WHITE);
+            }
+        }
+    }
+
+    image
+}
+
 impl Font {
     /// List of ascii characters, may be helpful in combination with "populate_font_cache"
     pub fn ascii_character_list() -> Vec<char> {
@@ -264,6 +332,22 @@ impl<'a> Default for TextParams<'a> {
     }
 }
 
+/// Arguments for "load_ttf_font_from_bytes_ex" function
+#[derive(Debug, Clone)]
+pub struct FontParams {
+    /// Initial size of the glyph atlas texture, in pixels.
+    /// The atlas doubles when it is full, but starting big avoids
+    /// re-uploading it while a lot of glyphs or large sizes are cached.
+    /// Default is 512
+    pub atlas_size: u16,
+}
+
+impl Default for FontParams {
+    fn default() -> FontParams {
+        FontParams { atlas_size: 512 }
+    }
+}
+
 /// Load font from file with "path"
 pub async fn load_ttf_font(path: &str) -> Result<Font, Error> {
     let bytes = crate::file::load_file(path)
@@ -278,9 +362,21 @@ pub async fn load_ttf_font(path: &str) -> Result<Font, Error> {
 /// let font = load_ttf_font_from_bytes(include_bytes!("font.ttf"));
 /// ```
 pub fn load_ttf_font_from_bytes(bytes: &[u8]) -> Result<Font, Error> {
-    let atlas = Arc::new(Mutex::new(Atlas::new(
+    load_ttf_font_from_bytes_ex(bytes, FontParams::default())
+}
+
+/// Load font from bytes array with custom params such as the atlas size
+/// ```ignore
+/// let font = load_ttf_font_from_bytes_ex(
+///     include_bytes!("cjk.ttf"),
+///     FontParams { atlas_size: 2048 },
+/// );
+/// ```
+pub fn load_ttf_font_from_bytes_ex(bytes: &[u8], params: FontParams) -> Result<Font, Error> {
+    let atlas = Arc::new(Mutex::new(Atlas::with_size(
         get_quad_context(),
         miniquad::FilterMode::Linear,
+        params.atlas_size,
     )));
 
     let mut font = Font::load_from_bytes(atlas.clone(), bytes)?;
@@ -386,6 +482,202 @@ pub fn draw_text_ex(text: &str, x: f32, y: f32, params: TextParams) -> TextDimen
     }
 }
 
+/// Horizontal anchor for "draw_text_aligned"
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HAlign {
+    Left,
+    Center,
+    Right,
+}
+
+/// Vertical anchor for "draw_text_aligned"
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VAlign {
+    /// Top of the tallest glyph
+    Top,
+    Center,
+    /// Same as "draw_text_ex"
+    Baseline,
+    /// Bottom of the lowest glyph
+    Bottom,
+}
+
+/// Draw text with the point [x, y] being the given anchor of its measured bounds.
+/// ```ignore
+/// // right aligned score in the top right corner
+/// draw_text_aligned(&score, screen_width() - 10., 10., params, HAlign::Right, VAlign::Top);
+/// ```
+/// Rotation is not taken into account while aligning.
+/// Returns text size
+pub fn draw_text_aligned(
+    text: &str,
+    x: f32,
+    y: f32,
+    params: TextParams,
+    halign: HAlign,
+    valign: VAlign,
+) -> TextDimensions {
+    let font = params
+        .font
+        .unwrap_or(&get_context().fonts_storage.default_font);
+    let size = font.measure_text(
+        text,
+        params.font_size,
+        params.font_scale * params.font_scale_aspect,
+        params.font_scale,
+    );
+
+    let origin = aligned_origin(x, y, size, halign, valign);
+
+    draw_text_ex(text, origin.x, origin.y, params)
+}
+
+/// Baseline origin for "draw_text_ex" putting the given anchor of `size` at [x, y].
+fn aligned_origin(x: f32, y: f32, size: TextDimensions, halign: HAlign, valign: VAlign) -> Vec2 {
+    let x = match halign {
+        HAlign::Left => x,
+        HAlign::Center => x - size.width / 2.,
+        HAlign::Right => x - size.width,
+    };
+    let y = match valign {
+        VAlign::Top => y + size.offset_y,
+        VAlign::Center => y + size.offset_y - size.height / 2.,
+        VAlign::Baseline => y,
+        VAlign::Bottom => y + size.offset_y - size.height,
+    };
+
+    vec2(x, y)
+}
+
+#[test]
+fn empty_text_is_not_offset() {
+    let size = TextDimensions {
+        width: 40.,
+        height: 10.,
+        offset_y: 8.,
+    };
+    assert_eq!(
+        aligned_origin(100., 50., size, HAlign::Center, VAlign::Center),
+        vec2(80., 53.)
+    );
+
+    for halign in [HAlign::Left, HAlign::Center, HAlign::Right] {
+        for valign in [
+            VAlign::Top,
+            VAlign::Center,
+            VAlign::Baseline,
+            VAlign::Bottom,
+        ] {
+            let origin = aligned_origin(100., 50., TextDimensions::default(), halign, valign);
+            assert_eq!(origin, vec2(100., 50.));
+        }
+    }
+}
+
+/// Draw text using a signed distance field of the font instead of per-size bitmaps.
+///
+/// Glyphs are rasterized once, at a fixed size, and the shader keeps their edges sharp
+/// at any "font_size" and "font_scale", which is handy for zoomed or animated text.
+/// Small sizes look a bit softer than with "draw_text_ex", so bitmap text stays the default.
+///
+/// Uses its own material, falls back to "draw_text_ex" if it can't be created
+/// (GLSL only, so not on Metal).
+/// Returns text size
+pub fn draw_text_sdf(text: &str, x: f32, y: f32, params: TextParams) -> TextDimensions {
+    if text.is_empty() {
+        return TextDimensions::default();
+    }
+
+    let material = match get_context().fonts_storage.sdf_material() {
+        Some(material) => material,
+        None => return draw_text_ex(text, x, y, params),
+    };
+
+    let font = params
+        .font
+        .unwrap_or(&get_context().fonts_storage.default_font);
+
+    let mut sdf = font.sdf.lock().unwrap();
+    let sdf = sdf.get_or_insert_with(|| SdfGlyphs {
+        atlas: Atlas::new(get_quad_context(), miniquad::FilterMode::Linear),
+        characters: HashMap::new(),
+    });
+
+    // unlike the bitmap glyphs, sdf glyphs do not depend on dpi:
+    // they are scaled from the fixed rasterization size right to the requested size
+    let size_scale = params.font_size as f32 / sdf::SDF_FONT_SIZE;
+    let font_scale_x = params.font_scale * params.font_scale_aspect * size_scale;
+    let font_scale_y = params.font_scale * size_scale;
+
+    // blend over about one physical pixel, whatever the scale is
+    let pixels_per_texel = font_scale_y * miniquad::window::dpi_scale();
+    let smoothing = (1. / (4. * sdf::SPREAD as f32 * pixels_per_texel)).min(0.25);
+
+    let previous_pipeline = get_context().gl.get_active_pipeline();
+    crate::material::gl_use_material(&material);
+    material.set_uniform("Smoothing", smoothing);
+
+    let rot = params.rotation;
+    let rot_cos = rot.cos();
+    let rot_sin = rot.sin();
+
+    let mut total_width = 0.0;
+    let mut max_offset_y = f32::MIN;
+    let mut min_offset_y = f32::MAX;
+    let spread = sdf::SPREAD as f32;
+
+    for character in text.chars() {
+        font.cache_sdf_glyph(character, sdf);
+
+        let char_data = &sdf.characters[&character];
+        let offset_x = char_data.offset_x as f32 * font_scale_x;
+        let offset_y = char_data.offset_y as f32 * font_scale_y;
+
+        let glyph = sdf.atlas.get(char_data.sprite).unwrap().rect;
+        let glyph_scaled_h = glyph.h * font_scale_y;
+
+        // the measured size should not include the distance field padding
+        min_offset_y = min_offset_y.min(offset_y + spread * font_scale_y);
+        max_offset_y = max_offset_y.max(glyph_scaled_h + offset_y - spread * font_scale_y);
+
+        let dest_x = (offset_x + total_width) * rot_cos + (glyph_scaled_h + offset_y) * rot_sin;
+        let dest_y = (offset_x + total_width) * rot_sin + (-glyph_scaled_h - offset_y) * rot_cos;
+
+        let dest = Rect::new(
+            dest_x + x,
+            dest_y + y,
+            glyph.w * font_scale_x,
+            glyph.h * font_scale_y,
+        );
+
+        total_width += char_data.advance * font_scale_x;
+
+        crate::texture::draw_texture_ex(
+            &crate::texture::Texture2D {
+                texture: TextureHandle::Unmanaged(sdf.atlas.texture()),
+            },
+            dest.x,
+            dest.y,
+            params.color,
+            crate::texture::DrawTextureParams {
+                dest_size: Some(vec2(dest.w, dest.h)),
+                source: Some(glyph),
+                rotation: rot,
+                pivot: Some(vec2(dest.x, dest.y)),
+                ..Default::default()
+            },
+        );
+    }
+
+    get_context().gl.pipeline(previous_pipeline);
+
+    TextDimensions {
+        width: total_width,
+        height: (max_offset_y - min_offset_y).max(0.),
+        offset_y: max_offset_y,
+    }
+}
+
 /// Draw multiline text with the given font_size, line_distance_factor and color.
 /// If no line distance but a custom font is given, the fonts line gap will be used as line distance factor if it exists.
 pub fn draw_multiline_text(
@@ -438,6 +730,258 @@ pub fn draw_multiline_text_ex(
     }
 }
 
+/// A single line of text made of runs with their own color and size,
+/// all sitting on the same baseline.
+///
+/// ```ignore
+/// let mut text = RichText::new(None, 20, WHITE);
+/// text.push("The goblin takes ")
+///     .push_colored("12 fire", ORANGE)
+///     .push(" damage")
+///     .push_sized("!", 30);
+/// text.draw(10., 40.);
+/// ```
+#[derive(Debug, Clone)]
+pub struct RichText {
+    font: Option<Font>,
+    font_size: u16,
+    color: Color,
+    runs: Vec<(String, u16, Color)>,
+}
+
+impl RichText {
+    /// Runs pushed without their own style use `font_size` and `color`.
+    pub fn new(font: Option<&Font>, font_size: u16, color: Color) -> RichText {
+        RichText {
+            font: font.cloned(),
+            font_size,
+            color,
+            runs: vec![],
+        }
+    }
+
+    pub fn push(&mut self, text: &str) -> &mut Self {
+        self.push_styled(text, self.font_size, self.color)
+    }
+
+    pub fn push_colored(&mut self, text: &str, color: Color) -> &mut Self {
+        self.push_styled(text, self.font_size, color)
+    }
+
+    pub fn push_sized(&mut self, text: &str, font_size: u16) -> &mut Self {
+        self.push_styled(text, font_size, self.color)
+    }
+
+    pub fn push_styled(&mut self, text: &str, font_size: u16, color: Color) -> &mut Self {
+        self.runs.push((text.to_owned(), font_size, color));
+        self
+    }
+
+    pub fn clear(&mut self) {
+        self.runs.clear();
+    }
+
+    /// Bounds of the whole line, `offset_y` is the distance from the top to the baseline.
+    pub fn measure(&self) -> TextDimensions {
+        let font = self
+            .font
+            .as_ref()
+            .unwrap_or(&get_context().fonts_storage.default_font);
+
+        Self::combine(
+            self.runs
+                .iter()
+                .map(|(text, font_size, _)| font.measure_text(text, *font_size, 1.0, 1.0)),
+        )
+    }
+
+    /// Draw the runs one after another, with the baseline at `y`.
+    pub fn draw(&self, x: f32, y: f32) -> TextDimensions {
+        let mut advance = 0.;
+
+        Self::combine(self.runs.iter().map(|(text, font_size, color)| {
+            let dimensions = draw_text_ex(
+                text,
+                x + advance,
+                y,
+                TextParams {
+                    font: self.font.as_ref(),
+                    font_size: *font_size,
+                    color: *color,
+                    ..Default::default()
+                },
+            );
+            advance += dimensions.width;
+            dimensions
+        }))
+    }
+
+    fn combine(runs: impl Iterator<Item = TextDimensions>) -> TextDimensions {
+        let (mut width, mut above, mut below) = (0.0f32, 0.0f32, 0.0f32);
+        for run in runs {
+            width += run.width;
+            above = above.max(run.offset_y);
+            below = below.max(run.height - run.offset_y);
+        }
+
+        TextDimensions {
+            width,
+            height: above + below,
+            offset_y: above,
+        }
+    }
+}
+
+/// Many strings of one font collected and drawn together, for things like
+/// hundreds of floating damage numbers.
+///
+/// `draw_text` emits a quad per glyph one by one, `TextBatch` keeps the
+/// positioned glyphs and draws them all in one go, as few geometry calls
+/// as the draw call capacity allows.
+///
+/// ```ignore
+/// let mut batch = TextBatch::new(None);
+/// loop {
+///     for number in &damage_numbers {
+///         batch.push(&number.text, number.x, number.y, 24., RED);
+///     }
+///     batch.draw();
+///     next_frame().await;
+/// }
+/// ```
+#[derive(Debug)]
+pub struct TextBatch {
+    font: Font,
+    glyphs: Vec<(SpriteKey, Rect, Color)>,
+}
+
+impl TextBatch {
+    // default draw call capacity is 5000 indices, 6 indices per glyph
+    const GLYPHS_PER_CALL: usize = 800;
+
+    /// A batch for the given font, or for the default one.
+    pub fn new(font: Option<&Font>) -> TextBatch {
+        TextBatch {
+            font: font
+                .unwrap_or(&get_context().fonts_storage.default_font)
+                .clone(),
+            glyphs: vec![],
+        }
+    }
+
+    /// Queue `text` with its baseline starting at `(x, y)`, same as `draw_text` would place it.
+    pub fn push(
+        &mut self,
+        text: &str,
+        x: f32,
+        y: f32,
+        font_size: f32,
+        color: Color,
+    ) -> TextDimensions {
+        if text.is_empty() {
+            return TextDimensions::default();
+        }
+
+        let dpi_scaling = miniquad::window::dpi_scale();
+        let font_size = (font_size * dpi_scaling).ceil() as u16;
+
+        let mut total_width = 0.0;
+        let mut max_offset_y = f32::MIN;
+        let mut min_offset_y = f32::MAX;
+
+        for character in text.chars() {
+            if !self.font.contains(character, font_size) {
+                self.font.cache_glyph(character, font_size);
+            }
+
+            let char_data = &self.font.characters.lock().unwrap()[&(character, font_size)];
+            let glyph = self
+                .font
+                .atlas
+                .lock()
+                .unwrap()
+                .get(char_data.sprite)
+                .unwrap()
+                .rect;
+            let offset_x = char_data.offset_x as f32;
+            let offset_y = char_data.offset_y as f32;
+
+            min_offset_y = min_offset_y.min(offset_y);
+            max_offset_y = max_offset_y.max(glyph.h + offset_y);
+
+            let dest = Rect::new(
+                (offset_x + total_width) / dpi_scaling + x,
+                (-glyph.h - offset_y) / dpi_scaling + y,
+                glyph.w / dpi_scaling,
+                glyph.h / dpi_scaling,
+            );
+            self.glyphs.push((char_data.sprite, dest, color));
+
+            total_width += char_data.advance;
+        }
+
+        TextDimensions {
+            width: total_width / dpi_scaling,
+            height: (max_offset_y - min_offset_y) / dpi_scaling,
+            offset_y: max_offset_y / dpi_scaling,
+        }
+    }
+
+    /// Number of glyphs waiting to be drawn.
+    pub fn len(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.glyphs.is_empty()
+    }
+
+    /// Forget everything pushed without drawing it.
+    pub fn clear(&mut self) {
+        self.glyphs.clear();
+    }
+
+    /// Draw everything pushed so far and clear the batch.
+    pub fn draw(&mut self) {
+        let context = get_context();
+        let mut atlas = self.font.atlas.lock().unwrap();
+        // glyphs cached after a push could have grown the atlas and moved the
+        // sprites, so source rects are only looked up now
+        let texture = crate::texture::Texture2D {
+            texture: TextureHandle::Unmanaged(atlas.texture()),
+        };
+        let (width, height) = (atlas.width() as f32, atlas.height() as f32);
+
+        let mut vertices = Vec::with_capacity(Self::GLYPHS_PER_CALL * 4);
+        let mut indices = Vec::with_capacity(Self::GLYPHS_PER_CALL * 6);
+        for chunk in self.glyphs.chunks(Self::GLYPHS_PER_CALL) {
+            vertices.clear();
+            indices.clear();
+
+            for (sprite, dest, color) in chunk {
+                let source = atlas.get(*sprite).unwrap().rect;
+                let (u0, v0) = (source.x / width, source.y / height);
+                let (u1, v1) = (source.right() / width, source.bottom() / height);
+
+                indices.extend([0, 1, 2, 0, 2, 3].map(|i| i + vertices.len() as u16));
+                #[rustfmt::skip]
+                vertices.extend([
+                    Vertex::new(dest.x         , dest.y         , 0., u0, v0, *color),
+                    Vertex::new(dest.x + dest.w, dest.y         , 0., u1, v0, *color),
+                    Vertex::new(dest.x + dest.w, dest.y + dest.h, 0., u1, v1, *color),
+                    Vertex::new(dest.x         , dest.y + dest.h, 0., u0, v1, *color),
+                ]);
+            }
+
+            context.gl.texture(Some(&texture));
+            context.gl.draw_mode(DrawMode::Triangles);
+            context.gl.geometry(&vertices, &indices);
+        }
+
+        self.glyphs.clear();
+    }
+}
+
 /// Get the text center.
 pub fn get_text_center(
     text: &str,
@@ -467,6 +1011,8 @@ pub fn measure_text(
 
 pub(crate) struct FontsStorage {
     default_font: Font,
+    // created on the first "draw_text_sdf", None inside if the shader failed to compile
+    sdf_material: Option<Option<Material>>,
 }
 
 impl FontsStorage {
@@ -474,7 +1020,14 @@ impl FontsStorage {
         let atlas = Arc::new(Mutex::new(Atlas::new(ctx, miniquad::FilterMode::Linear)));
 
         let default_font = Font::load_from_bytes(atlas, include_bytes!("ProggyClean.ttf")).unwrap();
-        FontsStorage { default_font }
+        FontsStorage {
+            default_font,
+            sdf_material: None,
+        }
+    }
+
+    fn sdf_material(&mut self) -> Option<Material> {
+        self.sdf_material.get_or_insert_with(sdf::material).clone()
     }
 }
 
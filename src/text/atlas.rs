@@ -41,7 +41,17 @@ impl Atlas {
     const UNIQUENESS_OFFSET: u64 = 100000;
 
     pub fn new(ctx: &mut dyn miniquad::RenderingBackend, filter: miniquad::FilterMode) -> Atlas {
-        let image = Image::gen_image_color(512, 512, Color::new(0.0, 0.0, 0.0, 0.0));
+        Self::with_size(ctx, filter, 512)
+    }
+
+    /// Atlas with the given initial size, it still grows when it runs out of space.
+    pub fn with_size(
+        ctx: &mut dyn miniquad::RenderingBackend,
+        filter: miniquad::FilterMode,
+        size: u16,
+    ) -> Atlas {
+        let size = size.max(1);
+        let image = Image::gen_image_color(size, size, Color::new(0.0, 0.0, 0.0, 0.0));
         let texture = ctx.new_texture_from_rgba8(image.width, image.height, &image.bytes);
         ctx.texture_set_filter(
             texture,
@@ -0,0 +1,133 @@
+//! Signed distance field glyphs, rendered once at a fixed size and scaled by the shader.
+
+use crate::{
+    material::{load_material, Material, MaterialParams},
+    texture::Image,
+};
+
+use miniquad::{BlendFactor, BlendState, BlendValue, Equation, PipelineParams, UniformDesc};
+
+/// Size glyphs are rasterized at before being turned into distance fields.
+pub(crate) const SDF_FONT_SIZE: f32 = 48.;
+/// How far, in pixels of the rasterized glyph, the distance field reaches out of the glyph.
+pub(crate) const SPREAD: usize = 6;
+
+/// Turn fontdue's coverage bitmap into a distance field image, padded by `spread`
+/// on each side. Alpha is 0.5 on the glyph edge and grows towards the inside.
+pub(crate) fn coverage_to_sdf(
+    coverage: &[u8],
+    width: usize,
+    height: usize,
+    spread: usize,
+) -> Image {
+    let inside = |x: isize, y: isize| {
+        x >= 0
+            && y >= 0
+            && (x as usize) < width
+            && (y as usize) < height
+            && coverage[y as usize * width + x as usize] >= 128
+    };
+
+    let (sdf_width, sdf_height) = (width + spread * 2, height + spread * 2);
+    let mut bytes = Vec::with_capacity(sdf_width * sdf_height * 4);
+    let spread = spread as isize;
+
+    for y in 0..sdf_height as isize {
+        for x in 0..sdf_width as isize {
+            let (sx, sy) = (x - spread, y - spread);
+            let is_inside = inside(sx, sy);
+
+            let mut distance = spread as f32 + 0.5;
+            for dy in -spread..=spread {
+                for dx in -spread..=spread {
+                    if inside(sx + dx, sy + dy) != is_inside {
+                        distance = distance.min(((dx * dx + dy * dy) as f32).sqrt());
+                    }
+                }
+            }
+
+            // the edge is half way between the pixel centers
+            let distance = distance - 0.5;
+            let signed = if is_inside { distance } else { -distance };
+            let value = (0.5 + signed / (spread as f32 * 2.)).clamp(0., 1.);
+
+            bytes.extend_from_slice(&[255, 255, 255, (value * 255.) as u8]);
+        }
+    }
+
+    Image {
+        bytes,
+        width: sdf_width as u16,
+        height: sdf_height as u16,
+    }
+}
+
+pub(crate) fn material() -> Option<Material> {
+    load_material(
+        crate::ShaderSource::Glsl {
+            vertex: VERTEX,
+            fragment: FRAGMENT,
+        },
+        MaterialParams {
+            pipeline_params: PipelineParams {
+                color_blend: Some(BlendState::new(
+                    Equation::Add,
+                    BlendFactor::Value(BlendValue::SourceAlpha),
+                    BlendFactor::OneMinusValue(BlendValue::SourceAlpha),
+                )),
+                ..Default::default()
+            },
+            uniforms: vec![UniformDesc::new("Smoothing", miniquad::UniformType::Float1)],
+            ..Default::default()
+        },
+    )
+    .ok()
+}
+
+const VERTEX: &str = r#"#version 100
+attribute vec3 position;
+attribute vec2 texcoord;
+attribute vec4 color0;
+
+varying lowp vec2 uv;
+varying lowp vec4 color;
+
+uniform mat4 Model;
+uniform mat4 Projection;
+
+void main() {
+    gl_Position = Projection * Model * vec4(position, 1);
+    color = color0 / 255.0;
+    uv = texcoord;
+}
+"#;
+
+const FRAGMENT: &str = r#"#version 100
+varying lowp vec4 color;
+varying lowp vec2 uv;
+
+uniform sampler2D Texture;
+uniform mediump float Smoothing;
+
+void main() {
+    mediump float distance = texture2D(Texture, uv).a;
+    mediump float alpha = smoothstep(0.5 - Smoothing, 0.5 + Smoothing, distance);
+    gl_FragColor = vec4(color.rgb, color.a * alpha);
+}
+"#;
+
+#[test]
+fn sdf_is_half_on_the_edge() {
+    // 4x4 filled square
+    let coverage = [255; 16];
+    let sdf = coverage_to_sdf(&coverage, 4, 4, 2);
+
+    assert_eq!((sdf.width, sdf.height), (8, 8));
+    let alpha = |x: usize, y: usize| sdf.bytes[(y * 8 + x) * 4 + 3];
+
+    // far outside, just outside, just inside, deep inside
+    assert_eq!(alpha(0, 0), 0);
+    assert!(alpha(1, 4) < 128);
+    assert!(alpha(2, 4) >= 128);
+    assert!(alpha(3, 4) > alpha(2, 4));
+}
@@ -1,18 +1,26 @@
 //! Loading and rendering textures. Also render textures, per-pixel image manipulations.
 
 use crate::{
-    color::Color, file::load_file, get_context, get_quad_context, math::Rect,
-    text::atlas::SpriteKey, Error,
+    color::Color,
+    file::load_file,
+    get_context, get_quad_context,
+    math::{Rect, RectOffset},
+    text::atlas::SpriteKey,
+    Error,
 };
 
 pub use crate::quad_gl::FilterMode;
 use crate::quad_gl::{DrawMode, Vertex};
 use glam::{vec2, Vec2};
+pub use miniquad::TextureWrap;
 use slotmap::{TextureIdSlotMap, TextureSlotId};
 use std::sync::Arc;
 
+mod indexed;
 mod slotmap;
 
+pub use indexed::{IndexedImage, IndexedTexture};
+
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) struct TextureSlotGuarded(pub TextureSlotId);
 
@@ -230,6 +238,147 @@ impl Image {
         }
     }
 
+    /// Returns a copy of this image scaled to `width` x `height`.
+    pub fn resized(&self, width: u16, height: u16, filter: ResizeFilter) -> Image {
+        let mut resized = Image::gen_image_color(width, height, Color::new(0., 0., 0., 0.));
+        if self.width == 0 || self.height == 0 {
+            return resized;
+        }
+
+        let scale_x = self.width as f32 / width as f32;
+        let scale_y = self.height as f32 / height as f32;
+        let pixel = |x: usize, y: usize| {
+            let ix = (y * self.width as usize + x) * 4;
+            &self.bytes[ix..ix + 4]
+        };
+
+        for y in 0..height as usize {
+            for x in 0..width as usize {
+                let color: [u8; 4] = match filter {
+                    ResizeFilter::Nearest => {
+                        let sx = ((x as f32 * scale_x) as usize).min(self.width as usize - 1);
+                        let sy = ((y as f32 * scale_y) as usize).min(self.height as usize - 1);
+                        pixel(sx, sy).try_into().unwrap()
+                    }
+                    ResizeFilter::Bilinear => {
+                        // sample between the centers of the four nearest source pixels
+                        let fx = ((x as f32 + 0.5) * scale_x - 0.5).max(0.);
+                        let fy = ((y as f32 + 0.5) * scale_y - 0.5).max(0.);
+                        let x0 = (fx as usize).min(self.width as usize - 1);
+                        let y0 = (fy as usize).min(self.height as usize - 1);
+                        let x1 = (x0 + 1).min(self.width as usize - 1);
+                        let y1 = (y0 + 1).min(self.height as usize - 1);
+                        let (tx, ty) = (fx - x0 as f32, fy - y0 as f32);
+
+                        std::array::from_fn(|i| {
+                            let top =
+                                pixel(x0, y0)[i] as f32 * (1. - tx) + pixel(x1, y0)[i] as f32 * tx;
+                            let bottom =
+                                pixel(x0, y1)[i] as f32 * (1. - tx) + pixel(x1, y1)[i] as f32 * tx;
+                            (top * (1. - ty) + bottom * ty).round() as u8
+                        })
+                    }
+                };
+                resized.get_image_data_mut()[y * width as usize + x] = color;
+            }
+        }
+
+        resized
+    }
+
+    /// Mirrors this image left to right.
+    pub fn flip_horizontal(&mut self) {
+        let width = self.width as usize;
+        for row in self.get_image_data_mut().chunks_exact_mut(width.max(1)) {
+            row.reverse();
+        }
+    }
+
+    /// Mirrors this image top to bottom.
+    pub fn flip_vertical(&mut self) {
+        let row = self.width as usize * 4;
+        let height = self.height as usize;
+        for y in 0..height / 2 {
+            let (top, bottom) = self.bytes.split_at_mut((height - y - 1) * row);
+            top[y * row..(y + 1) * row].swap_with_slice(&mut bottom[..row]);
+        }
+    }
+
+    /// Rotates this image by 90 degrees clockwise, swapping width and height.
+    pub fn rotate_90_cw(&mut self) {
+        let height = self.height as usize;
+        self.rotate_90(|x, y| (height - 1 - y, x));
+    }
+
+    /// Rotates this image by 90 degrees counter-clockwise, swapping width and height.
+    pub fn rotate_90_ccw(&mut self) {
+        let width = self.width as usize;
+        self.rotate_90(|x, y| (y, width - 1 - x));
+    }
+
+    // `destination(x, y)` is where the source pixel (x, y) ends up in the rotated image
+    fn rotate_90(&mut self, destination: impl Fn(usize, usize) -> (usize, usize)) {
+        let source = self.get_image_data().to_vec();
+        let (width, height) = (self.width as usize, self.height as usize);
+        std::mem::swap(&mut self.width, &mut self.height);
+
+        let data = self.get_image_data_mut();
+        for y in 0..height {
+            for x in 0..width {
+                let (dx, dy) = destination(x, y);
+                data[dy * height + dx] = source[y * width + x];
+            }
+        }
+    }
+
+    /// Returns a blurred copy of this image, the blur reaches `radius` pixels around each pixel.
+    ///
+    /// Pixels past the borders repeat the border ones, so edges don't fade out.
+    pub fn blur(&self, radius: f32, kind: BlurKind) -> Image {
+        let reach = radius.max(0.).ceil() as isize;
+        let kernel = (-reach..=reach)
+            .map(|i| match kind {
+                BlurKind::Box => 1.,
+                BlurKind::Gaussian => {
+                    // most of the bell fits in 3 sigmas
+                    let sigma = (radius / 3.).max(0.001);
+                    (-(i * i) as f32 / (2. * sigma * sigma)).exp()
+                }
+            })
+            .collect::<Vec<f32>>();
+        let sum: f32 = kernel.iter().sum();
+        let kernel = kernel.iter().map(|weight| weight / sum).collect::<Vec<_>>();
+
+        let horizontal = self.blur_pass(&kernel, 1, 0);
+        horizontal.blur_pass(&kernel, 0, 1)
+    }
+
+    // one direction of a separable blur
+    fn blur_pass(&self, kernel: &[f32], dx: isize, dy: isize) -> Image {
+        let (width, height) = (self.width as isize, self.height as isize);
+        let reach = kernel.len() as isize / 2;
+        let source = self.get_image_data();
+
+        let mut blurred = self.clone();
+        let data = blurred.get_image_data_mut();
+        for y in 0..height {
+            for x in 0..width {
+                let mut color = [0.; 4];
+                for (i, weight) in kernel.iter().enumerate() {
+                    let offset = i as isize - reach;
+                    let sx = (x + offset * dx).clamp(0, width - 1);
+                    let sy = (y + offset * dy).clamp(0, height - 1);
+                    let pixel = source[(sy * width + sx) as usize];
+                    for c in 0..4 {
+                        color[c] += pixel[c] as f32 * weight;
+                    }
+                }
+                data[(y * width + x) as usize] = color.map(|c| c.round() as u8);
+            }
+        }
+        blurred
+    }
+
     /// Blends this image with another image (of identical dimensions)
     /// Inspired by  OpenCV saturated blending
     pub fn blend(&mut self, other: &Image) {
@@ -304,19 +453,13 @@ impl Image {
     /// Saves this image as a PNG file.
     /// This method is not supported on web and will panic.
     pub fn export_png(&self, path: &str) {
-        let mut bytes = vec![0; self.width as usize * self.height as usize * 4];
-
         // flip the image before saving
-        for y in 0..self.height as usize {
-            for x in 0..self.width as usize * 4 {
-                bytes[y * self.width as usize * 4 + x] =
-                    self.bytes[(self.height as usize - y - 1) * self.width as usize * 4 + x];
-            }
-        }
+        let mut flipped = self.clone();
+        flipped.flip_vertical();
 
         image::save_buffer(
             path,
-            &bytes[..],
+            &flipped.bytes[..],
             self.width as _,
             self.height as _,
             image::ColorType::Rgba8,
@@ -325,6 +468,88 @@ impl Image {
     }
 }
 
+/// How [Image::resized] samples the source image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResizeFilter {
+    /// Closest pixel, keeps pixel art sharp.
+    Nearest,
+    /// Weighted average of the four closest pixels.
+    Bilinear,
+}
+
+#[test]
+fn resize_checker_nearest() {
+    let mut checker = Image::gen_image_color(2, 2, Color::new(0., 0., 0., 1.));
+    checker.set_pixel(0, 0, Color::new(1., 1., 1., 1.));
+    checker.set_pixel(1, 1, Color::new(1., 1., 1., 1.));
+
+    let resized = checker.resized(4, 4, ResizeFilter::Nearest);
+    assert_eq!((resized.width, resized.height), (4, 4));
+    let white = [255, 255, 255, 255];
+    let black = [0, 0, 0, 255];
+    let data = resized.get_image_data();
+    assert_eq!(data[0], white);
+    assert_eq!(data[1], white);
+    assert_eq!(data[3], black);
+    assert_eq!(data[12], black);
+    assert_eq!(data[15], white);
+
+    let smooth = checker.resized(4, 4, ResizeFilter::Bilinear);
+    assert_eq!(smooth.get_image_data()[0], white);
+    let center = smooth.get_image_data()[5];
+    assert!(center[0] > 0 && center[0] < 255);
+}
+
+#[test]
+fn flips_and_rotations_move_pixels() {
+    let red = Color::new(1., 0., 0., 1.);
+    let mut image = Image::gen_image_color(3, 2, Color::new(0., 0., 0., 1.));
+    image.set_pixel(0, 0, red);
+
+    let mut flipped = image.clone();
+    flipped.flip_horizontal();
+    assert_eq!(flipped.get_pixel(2, 0), red);
+    flipped.flip_vertical();
+    assert_eq!(flipped.get_pixel(2, 1), red);
+
+    let mut rotated = image.clone();
+    rotated.rotate_90_cw();
+    assert_eq!((rotated.width, rotated.height), (2, 3));
+    assert_eq!(rotated.get_pixel(1, 0), red);
+
+    let mut rotated = image.clone();
+    rotated.rotate_90_ccw();
+    assert_eq!((rotated.width, rotated.height), (2, 3));
+    assert_eq!(rotated.get_pixel(0, 2), red);
+}
+
+/// Kernel used by [Image::blur].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlurKind {
+    /// Plain average, cheap and a bit blocky.
+    Box,
+    /// Smooth falloff from the center.
+    Gaussian,
+}
+
+#[test]
+fn gaussian_blur_falls_off() {
+    let mut image = Image::gen_image_color(9, 9, Color::new(0., 0., 0., 1.));
+    image.set_pixel(4, 4, Color::new(1., 1., 1., 1.));
+
+    let blurred = image.blur(3., BlurKind::Gaussian);
+    let red = |x: usize, y: usize| blurred.get_image_data()[y * 9 + x][0];
+    assert!(red(4, 4) > red(5, 4));
+    assert!(red(5, 4) > red(6, 4));
+    assert_eq!(red(3, 4), red(5, 4));
+    assert_eq!(red(4, 3), red(4, 5));
+    assert_eq!(red(0, 0), 0);
+
+    // the border pixels are clamped, a flat image stays flat
+    let flat = Image::gen_image_color(4, 4, Color::new(1., 1., 1., 1.)).blur(2., BlurKind::Box);
+    assert!(flat.get_image_data().iter().all(|pixel| *pixel == [255; 4]));
+}
+
 /// Loads an [Image] from a file into CPU memory.
 pub async fn load_image(path: &str) -> Result<Image, Error> {
     let bytes = load_file(path).await?;
@@ -339,6 +564,16 @@ pub async fn load_texture(path: &str) -> Result<Texture2D, Error> {
     Ok(Texture2D::from_file_with_format(&bytes[..], None))
 }
 
+/// Loads a [Texture2D] from a file into GPU memory, blocking until the file is loaded.
+///
+/// Not available on web, use `load_texture` there.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_texture_blocking(path: &str) -> Result<Texture2D, Error> {
+    let bytes = crate::file::load_file_blocking(path)?;
+
+    Ok(Texture2D::from_file_with_format(&bytes[..], None))
+}
+
 #[derive(Debug, Clone)]
 pub struct RenderPass {
     pub color_texture: Texture2D,
@@ -600,6 +835,203 @@ pub fn draw_texture_ex(
     context.gl.geometry(&vertices, &indices);
 }
 
+/// Draw a texture mapped onto an arbitrary quad, for skewed sprites,
+/// trapezoid shadows and other deformations.
+///
+/// `uvs` are normalized texture coordinates,
+/// `[vec2(0., 0.), vec2(1., 0.), vec2(1., 1.), vec2(0., 1.)]` maps the whole texture. Corners are expected in the order top-left, top-right,
+/// bottom-right, bottom-left and the quad is drawn as triangles (0, 1, 2) and (0, 2, 3),
+/// so that order is clockwise with the default y-down camera. Keep it in mind
+/// with a material that culls faces.
+pub fn draw_texture_quad(texture: &Texture2D, corners: [Vec2; 4], uvs: [Vec2; 4], color: Color) {
+    let context = get_context();
+
+    let (texture, uv) = match context.texture_batcher.get(texture) {
+        Some((batched_texture, uv)) => (batched_texture, uv),
+        None => (texture.clone(), Rect::new(0., 0., 1., 1.)),
+    };
+
+    let vertices = std::array::from_fn::<_, 4, _>(|i| {
+        Vertex::new(
+            corners[i].x,
+            corners[i].y,
+            0.,
+            uvs[i].x * uv.w + uv.x,
+            uvs[i].y * uv.h + uv.y,
+            color,
+        )
+    });
+    let indices: [u16; 6] = [0, 1, 2, 0, 2, 3];
+
+    context.gl.texture(Some(&texture));
+    context.gl.draw_mode(DrawMode::Triangles);
+    context.gl.geometry(&vertices, &indices);
+}
+
+/// Fill `dest` with copies of a texture, each `tile_size` big, starting at the top left corner.
+/// Tiles on the right and bottom edges are cropped to fit.
+///
+/// All the copies go into one geometry call, so this works with any texture,
+/// including the ones packed into the texture atlas. For textures that are
+/// never batched, `Texture2D::set_wrap(TextureWrap::Repeat)` and a single quad
+/// with UVs above 1 is the even cheaper alternative.
+pub fn draw_texture_tiled(texture: &Texture2D, dest: Rect, tile_size: Vec2, color: Color) {
+    // default draw call capacity is 5000 indices, 6 indices per tile
+    const TILES_PER_CALL: usize = 800;
+
+    if tile_size.x <= 0. || tile_size.y <= 0. || dest.w <= 0. || dest.h <= 0. {
+        return;
+    }
+
+    let context = get_context();
+
+    let (texture, uv) = match context.texture_batcher.get(texture) {
+        Some((batched_texture, uv)) => (batched_texture, uv),
+        None => (texture.clone(), Rect::new(0., 0., 1., 1.)),
+    };
+
+    let columns = (dest.w / tile_size.x).ceil() as usize;
+    let rows = (dest.h / tile_size.y).ceil() as usize;
+    let tiles = (0..rows).flat_map(|row| (0..columns).map(move |column| (column, row)));
+
+    let mut vertices = Vec::<Vertex>::with_capacity(TILES_PER_CALL * 4);
+    let mut indices = Vec::<u16>::with_capacity(TILES_PER_CALL * 6);
+    for (column, row) in tiles {
+        let x = dest.x + column as f32 * tile_size.x;
+        let y = dest.y + row as f32 * tile_size.y;
+        let w = tile_size.x.min(dest.right() - x);
+        let h = tile_size.y.min(dest.bottom() - y);
+
+        let u0 = uv.x;
+        let v0 = uv.y;
+        let u1 = uv.x + uv.w * w / tile_size.x;
+        let v1 = uv.y + uv.h * h / tile_size.y;
+
+        indices.extend([0, 1, 2, 0, 2, 3].map(|i| i + vertices.len() as u16));
+        #[rustfmt::skip]
+        vertices.extend([
+            Vertex::new(x    , y    , 0., u0, v0, color),
+            Vertex::new(x + w, y    , 0., u1, v0, color),
+            Vertex::new(x + w, y + h, 0., u1, v1, color),
+            Vertex::new(x    , y + h, 0., u0, v1, color),
+        ]);
+
+        if vertices.len() == TILES_PER_CALL * 4 {
+            context.gl.texture(Some(&texture));
+            context.gl.draw_mode(DrawMode::Triangles);
+            context.gl.geometry(&vertices, &indices);
+            vertices.clear();
+            indices.clear();
+        }
+    }
+
+    if !vertices.is_empty() {
+        context.gl.texture(Some(&texture));
+        context.gl.draw_mode(DrawMode::Triangles);
+        context.gl.geometry(&vertices, &indices);
+    }
+}
+
+/// Draw a texture as a 9-patch stretched over the `dest` rect.
+///
+/// `border` is in texture pixels: the corners are drawn unscaled,
+/// edges are stretched along one axis and the center along both.
+/// If `dest` is smaller than the borders, the borders shrink proportionally.
+pub fn draw_texture_nine_slice(texture: &Texture2D, dest: Rect, border: RectOffset, color: Color) {
+    let context = get_context();
+
+    let [width, height] = texture.size().to_array();
+    let source = Rect::new(0., 0., width, height);
+
+    let (texture, uv) = match context.texture_batcher.get(texture) {
+        Some((batched_texture, uv)) => (batched_texture, uv),
+        None => (texture.clone(), Rect::new(0., 0., 1., 1.)),
+    };
+
+    let mut vertices = Vec::<Vertex>::with_capacity(9 * 4);
+    let mut indices = Vec::<u16>::with_capacity(9 * 6);
+    for (source, dest) in nine_slice_rects(source, dest, border) {
+        let u0 = source.x / width * uv.w + uv.x;
+        let v0 = source.y / height * uv.h + uv.y;
+        let u1 = (source.x + source.w) / width * uv.w + uv.x;
+        let v1 = (source.y + source.h) / height * uv.h + uv.y;
+
+        indices.extend([0, 1, 2, 0, 2, 3].map(|i| i + vertices.len() as u16));
+        #[rustfmt::skip]
+        vertices.extend([
+            Vertex::new(dest.x         , dest.y         , 0., u0, v0, color),
+            Vertex::new(dest.x + dest.w, dest.y         , 0., u1, v0, color),
+            Vertex::new(dest.x + dest.w, dest.y + dest.h, 0., u1, v1, color),
+            Vertex::new(dest.x         , dest.y + dest.h, 0., u0, v1, color),
+        ]);
+    }
+
+    context.gl.texture(Some(&texture));
+    context.gl.draw_mode(DrawMode::Triangles);
+    context.gl.geometry(&vertices, &indices);
+}
+
+/// (source, dest) pairs of the nine 9-patch regions, row by row from the top left.
+fn nine_slice_rects(source: Rect, dest: Rect, border: RectOffset) -> [(Rect, Rect); 9] {
+    // shrink the borders when they don't fit into dest
+    let scale_x = (dest.w / (border.left + border.right)).min(1.);
+    let scale_y = (dest.h / (border.top + border.bottom)).min(1.);
+    let (left, right) = (border.left * scale_x, border.right * scale_x);
+    let (top, bottom) = (border.top * scale_y, border.bottom * scale_y);
+
+    let source_xs = [
+        (source.x, border.left),
+        (
+            source.x + border.left,
+            source.w - border.left - border.right,
+        ),
+        (source.x + source.w - border.right, border.right),
+    ];
+    let source_ys = [
+        (source.y, border.top),
+        (source.y + border.top, source.h - border.top - border.bottom),
+        (source.y + source.h - border.bottom, border.bottom),
+    ];
+    let dest_xs = [
+        (dest.x, left),
+        (dest.x + left, dest.w - left - right),
+        (dest.x + dest.w - right, right),
+    ];
+    let dest_ys = [
+        (dest.y, top),
+        (dest.y + top, dest.h - top - bottom),
+        (dest.y + dest.h - bottom, bottom),
+    ];
+
+    std::array::from_fn(|i| {
+        let (row, column) = (i / 3, i % 3);
+        let ((sx, sw), (sy, sh)) = (source_xs[column], source_ys[row]);
+        let ((dx, dw), (dy, dh)) = (dest_xs[column], dest_ys[row]);
+
+        (Rect::new(sx, sy, sw, sh), Rect::new(dx, dy, dw, dh))
+    })
+}
+
+#[test]
+fn nine_slice_keeps_corners() {
+    let source = Rect::new(0., 0., 32., 32.);
+    let dest = Rect::new(100., 50., 200., 80.);
+    let border = RectOffset::new(4., 6., 5., 7.);
+    let rects = nine_slice_rects(source, dest, border);
+
+    let (top_left, top_left_dest) = rects[0];
+    assert_eq!(top_left, Rect::new(0., 0., 4., 5.));
+    assert_eq!(top_left_dest, Rect::new(100., 50., 4., 5.));
+
+    let (bottom_right, bottom_right_dest) = rects[8];
+    assert_eq!(bottom_right, Rect::new(26., 25., 6., 7.));
+    assert_eq!(bottom_right_dest, Rect::new(294., 123., 6., 7.));
+
+    let (center, center_dest) = rects[4];
+    assert_eq!(center, Rect::new(4., 5., 22., 20.));
+    assert_eq!(center_dest, Rect::new(104., 55., 190., 68.));
+}
+
 /// Get pixel data from screen buffer and return an Image (screenshot)
 pub fn get_screen_data() -> Image {
     unsafe {
@@ -730,14 +1162,21 @@ impl Texture2D {
     /// # }
     /// ```
     pub fn from_rgba8(width: u16, height: u16, bytes: &[u8]) -> Texture2D {
+        let texture = Texture2D::from_rgba8_unbatched(width, height, bytes);
+        get_context().texture_batcher.add_unbatched(&texture);
+
+        texture
+    }
+
+    /// Same as [Texture2D::from_rgba8], but [build_textures_atlas] leaves it out,
+    /// for textures whose texels should never be filtered or moved.
+    pub(crate) fn from_rgba8_unbatched(width: u16, height: u16, bytes: &[u8]) -> Texture2D {
         let texture = get_quad_context().new_texture_from_rgba8(width, height, bytes);
         let ctx = get_context();
         let texture = ctx.textures.store_texture(texture);
         let texture = Texture2D { texture };
         texture.set_filter(ctx.default_filter_mode);
 
-        ctx.texture_batcher.add_unbatched(&texture);
-
         texture
     }
 
@@ -830,6 +1269,16 @@ impl Texture2D {
         );
     }
 
+    /// Sets how UVs outside of 0..1 sample this texture, both horizontally and vertically.
+    ///
+    /// Textures are clamped by default. Small textures may be packed into the shared
+    /// texture atlas, where repeat has no effect, see `draw_texture_tiled`.
+    pub fn set_wrap(&self, wrap: TextureWrap) {
+        let ctx = get_quad_context();
+
+        ctx.texture_set_wrap(self.raw_miniquad_id(), wrap, wrap);
+    }
+
     /// Returns the handle for this texture.
     pub fn raw_miniquad_id(&self) -> miniquad::TextureId {
         let ctx = get_context();
@@ -888,6 +1337,292 @@ impl Texture2D {
         ctx.texture_read_pixels(self.raw_miniquad_id(), &mut image.bytes);
         image
     }
+
+    /// Reads back a single pixel of this texture.
+    ///
+    /// Pending draw calls are flushed first, so this works right after drawing
+    /// into a render target. `y` goes bottom-up, the way render target textures
+    /// are stored.
+    ///
+    /// Meant for color-id picking: draw every object into a render target
+    /// with a color unique to the object, then read the pixel under the mouse.
+    ///
+    /// ```ignore
+    /// let target = render_target(screen_width() as u32, screen_height() as u32);
+    /// set_camera(&Camera2D {
+    ///     render_target: Some(target.clone()),
+    ///     ..camera
+    /// });
+    /// clear_background(BLACK);
+    /// for (id, object) in objects.iter().enumerate() {
+    ///     // id 0 is left for the background
+    ///     let id = id as u32 + 1;
+    ///     object.draw_with_color(Color::from_rgba(id as u8, (id >> 8) as u8, 0, 255));
+    /// }
+    /// set_default_camera();
+    ///
+    /// let (x, y) = mouse_position();
+    /// let pixel: [u8; 4] = target
+    ///     .texture
+    ///     .read_pixel(x as u32, screen_height() as u32 - 1 - y as u32)
+    ///     .into();
+    /// let picked = pixel[0] as u32 | (pixel[1] as u32) << 8;
+    /// ```
+    ///
+    /// Only RGBA8 textures give meaningful results.
+    #[allow(unreachable_patterns)]
+    pub fn read_pixel(&self, x: u32, y: u32) -> Color {
+        use miniquad::gl;
+
+        unsafe {
+            crate::window::get_internal_gl().flush();
+        }
+
+        let texture = self.raw_miniquad_id();
+        let ctx = get_quad_context();
+        let raw_id = match unsafe { ctx.texture_raw_id(texture) } {
+            miniquad::RawId::OpenGl(id) => id,
+            // no raw access to other backends, read the whole texture instead
+            _ => return self.get_texture_data().get_pixel(x, y),
+        };
+
+        let mut pixel = [0u8; 4];
+        unsafe {
+            let mut binding = 0;
+            gl::glGetIntegerv(gl::GL_FRAMEBUFFER_BINDING, &mut binding);
+
+            let mut fbo = 0;
+            gl::glGenFramebuffers(1, &mut fbo);
+            gl::glBindFramebuffer(gl::GL_FRAMEBUFFER, fbo);
+            gl::glFramebufferTexture2D(
+                gl::GL_FRAMEBUFFER,
+                gl::GL_COLOR_ATTACHMENT0,
+                gl::GL_TEXTURE_2D,
+                raw_id,
+                0,
+            );
+            gl::glReadPixels(
+                x as _,
+                y as _,
+                1,
+                1,
+                gl::GL_RGBA,
+                gl::GL_UNSIGNED_BYTE,
+                pixel.as_mut_ptr() as _,
+            );
+
+            gl::glBindFramebuffer(gl::GL_FRAMEBUFFER, binding as _);
+            gl::glDeleteFramebuffers(1, &fbo);
+        }
+
+        pixel.into()
+    }
+}
+
+/// Packs many small images into a single texture at load time.
+///
+/// Everything drawn from one texture goes into one draw call, so a
+/// sprite-heavy game can draw all of its sprites at once.
+///
+/// ```ignore
+/// let mut builder = TextureAtlasBuilder::new();
+/// builder.add("player", load_image("player.png").await.unwrap());
+/// builder.add("coin", load_image("coin.png").await.unwrap());
+/// let atlas = builder.build().unwrap();
+///
+/// draw_texture_ex(
+///     &atlas.texture,
+///     x,
+///     y,
+///     WHITE,
+///     DrawTextureParams {
+///         source: atlas.get(&"coin"),
+///         ..Default::default()
+///     },
+/// );
+/// ```
+#[derive(Debug)]
+pub struct TextureAtlasBuilder<K> {
+    /// Transparent pixels around each image, keeps linear filtering from
+    /// bleeding neighbours into each other.
+    pub gap: u16,
+    images: Vec<(K, Image)>,
+}
+
+/// A texture built by [TextureAtlasBuilder], with the source rect of every packed image.
+#[derive(Debug, Clone)]
+pub struct TextureAtlas<K> {
+    pub texture: Texture2D,
+    pub rects: std::collections::HashMap<K, Rect>,
+}
+
+impl<K: std::hash::Hash + Eq> TextureAtlas<K> {
+    /// Source rect of the image added with this key, ready for `DrawTextureParams::source`.
+    pub fn get(&self, key: &K) -> Option<Rect> {
+        self.rects.get(key).copied()
+    }
+}
+
+impl<K: std::hash::Hash + Eq> Default for TextureAtlasBuilder<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: std::hash::Hash + Eq> TextureAtlasBuilder<K> {
+    pub fn new() -> Self {
+        TextureAtlasBuilder {
+            gap: 2,
+            images: vec![],
+        }
+    }
+
+    pub fn add(&mut self, key: K, image: Image) {
+        self.images.push((key, image));
+    }
+
+    /// Packs the images into an [Image] and returns it with the source rects.
+    ///
+    /// Fails when the images do not fit in a 65535x65535 image.
+    pub fn build_image(self) -> Result<(Image, std::collections::HashMap<K, Rect>), Error> {
+        self.build_image_with_limit(u16::MAX as u32)
+    }
+
+    fn build_image_with_limit(
+        self,
+        max_size: u32,
+    ) -> Result<(Image, std::collections::HashMap<K, Rect>), Error> {
+        let sizes = self
+            .images
+            .iter()
+            .map(|(_, image)| (image.width, image.height))
+            .collect::<Vec<_>>();
+        let (width, height, positions) = pack_shelves(&sizes, self.gap);
+        check_atlas_size(width, height, max_size.min(u16::MAX as u32))?;
+        let (width, height) = (width as u16, height as u16);
+
+        let mut atlas = Image::gen_image_color(width, height, Color::new(0., 0., 0., 0.));
+        let mut rects = std::collections::HashMap::with_capacity(self.images.len());
+        for ((key, image), (x, y)) in self.images.into_iter().zip(positions) {
+            let row = image.width as usize * 4;
+            for line in 0..image.height as usize {
+                let dest = ((y as usize + line) * width as usize + x as usize) * 4;
+                atlas.bytes[dest..dest + row]
+                    .copy_from_slice(&image.bytes[line * row..(line + 1) * row]);
+            }
+            rects.insert(
+                key,
+                Rect::new(x as f32, y as f32, image.width as f32, image.height as f32),
+            );
+        }
+
+        Ok((atlas, rects))
+    }
+
+    /// Packs the images and uploads them into a new texture.
+    ///
+    /// Fails when the atlas would be bigger than the GPU supports. The limit is
+    /// only queried on OpenGL, other backends are checked against 65535x65535 only.
+    pub fn build(self) -> Result<TextureAtlas<K>, Error> {
+        let (image, rects) = self.build_image_with_limit(max_texture_size())?;
+
+        Ok(TextureAtlas {
+            texture: Texture2D::from_image(&image),
+            rects,
+        })
+    }
+}
+
+/// GL_MAX_TEXTURE_SIZE, or `u32::MAX` when the backend gives no raw GL access.
+fn max_texture_size() -> u32 {
+    use miniquad::gl;
+
+    if get_quad_context().info().backend != miniquad::Backend::OpenGl {
+        return u32::MAX;
+    }
+    let mut size = 0;
+    unsafe {
+        gl::glGetIntegerv(gl::GL_MAX_TEXTURE_SIZE, &mut size);
+    }
+    size as u32
+}
+
+fn check_atlas_size(width: u32, height: u32, max_size: u32) -> Result<(), Error> {
+    if width > max_size || height > max_size {
+        return Err(Error::TextureAtlasTooBig {
+            width,
+            height,
+            max_size,
+        });
+    }
+    Ok(())
+}
+
+/// Shelf packing: images are sorted by height and laid out in rows, the atlas
+/// width is the next power of two fitting all the images in a roughly square area.
+/// Returns atlas width, height and the position of each image, in input order.
+fn pack_shelves(sizes: &[(u16, u16)], gap: u16) -> (u32, u32, Vec<(u32, u32)>) {
+    let padded = |(w, h): (u16, u16)| (w as u32 + gap as u32 * 2, h as u32 + gap as u32 * 2);
+
+    let area: u64 = sizes
+        .iter()
+        .map(|&size| padded(size))
+        .map(|(w, h)| w as u64 * h as u64)
+        .sum();
+    let widest = sizes.iter().map(|&size| padded(size).0).max().unwrap_or(1);
+    let width = ((area as f64).sqrt().ceil() as u32)
+        .max(widest)
+        .next_power_of_two();
+
+    let mut order = (0..sizes.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| std::cmp::Reverse(sizes[i].1));
+
+    let mut positions = vec![(0, 0); sizes.len()];
+    let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+    for i in order {
+        let (w, h) = padded(sizes[i]);
+        if x + w > width {
+            x = 0;
+            y += shelf_height;
+            shelf_height = 0;
+        }
+        positions[i] = (x + gap as u32, y + gap as u32);
+        x += w;
+        shelf_height = shelf_height.max(h);
+    }
+
+    (width, (y + shelf_height).max(1), positions)
+}
+
+#[test]
+fn shelf_packing_does_not_overlap() {
+    let sizes = [(10, 20), (30, 5), (16, 16), (64, 8), (1, 1), (20, 20)];
+    let (width, height, positions) = pack_shelves(&sizes, 1);
+
+    let rects = sizes
+        .iter()
+        .zip(&positions)
+        .map(|(&(w, h), &(x, y))| Rect::new(x as f32, y as f32, w as f32, h as f32))
+        .collect::<Vec<_>>();
+
+    for (i, a) in rects.iter().enumerate() {
+        assert!(a.x >= 1. && a.y >= 1.);
+        assert!(a.right() < width as f32 && a.bottom() < height as f32);
+        for b in &rects[i + 1..] {
+            assert!(a.intersect(*b).is_none(), "{:?} overlaps {:?}", a, b);
+        }
+    }
+}
+
+#[test]
+fn oversized_atlas_is_an_error() {
+    let (width, height, _) = pack_shelves(&[(u16::MAX, 10)], 1);
+    assert!(width > u16::MAX as u32);
+    assert!(check_atlas_size(width, height, u16::MAX as u32).is_err());
+
+    let (width, height, _) = pack_shelves(&[(4000, 10), (10, 10)], 1);
+    assert!(check_atlas_size(width, height, u16::MAX as u32).is_ok());
+    assert!(check_atlas_size(width, height, 2048).is_err());
 }
 
 pub(crate) struct Batcher {
@@ -0,0 +1,248 @@
+//! Palette based images, for palette swaps: team colors, day/night, damage flashes.
+
+use crate::{
+    color::Color,
+    get_context,
+    material::{gl_use_material, load_material, Material, MaterialParams},
+    texture::{draw_texture_ex, DrawTextureParams, FilterMode, Image, Texture2D},
+    window::get_internal_gl,
+    Error,
+};
+
+use miniquad::{BlendFactor, BlendState, BlendValue, Equation, PipelineParams};
+
+/// Image where every pixel is an index into a 256 colors palette.
+#[derive(Clone, Debug)]
+pub struct IndexedImage {
+    pub width: u16,
+    pub height: u16,
+    pub indices: Vec<u8>,
+    pub palette: [Color; 256],
+}
+
+impl IndexedImage {
+    /// Image filled with palette index 0.
+    pub fn new(width: u16, height: u16, palette: [Color; 256]) -> IndexedImage {
+        IndexedImage {
+            width,
+            height,
+            indices: vec![0; width as usize * height as usize],
+            palette,
+        }
+    }
+
+    /// Build the palette out of the colors used by `image`, in order of appearance.
+    /// Returns `None` if the image has more than 256 distinct colors.
+    pub fn from_image(image: &Image) -> Option<IndexedImage> {
+        let mut colors: Vec<[u8; 4]> = vec![];
+        let mut indices = Vec::with_capacity(image.width() * image.height());
+
+        for pixel in image.get_image_data() {
+            let index = match colors.iter().position(|color| color == pixel) {
+                Some(index) => index,
+                None if colors.len() < 256 => {
+                    colors.push(*pixel);
+                    colors.len() - 1
+                }
+                None => return None,
+            };
+            indices.push(index as u8);
+        }
+
+        let mut palette = [Color::new(0., 0., 0., 0.); 256];
+        for (entry, color) in palette.iter_mut().zip(colors) {
+            *entry = color.into();
+        }
+
+        Some(IndexedImage {
+            width: image.width,
+            height: image.height,
+            indices,
+            palette,
+        })
+    }
+
+    pub fn get_index(&self, x: u32, y: u32) -> u8 {
+        self.indices[(y * self.width as u32 + x) as usize]
+    }
+
+    pub fn set_index(&mut self, x: u32, y: u32, index: u8) {
+        assert!(x < self.width as u32);
+        assert!(y < self.height as u32);
+
+        self.indices[(y * self.width as u32 + x) as usize] = index;
+    }
+
+    /// Resolve every pixel through the palette.
+    pub fn to_rgba(&self) -> Image {
+        let mut image = Image::gen_image_color(self.width, self.height, Color::new(0., 0., 0., 0.));
+        for (pixel, index) in image.get_image_data_mut().iter_mut().zip(&self.indices) {
+            *pixel = self.palette[*index as usize].into();
+        }
+        image
+    }
+}
+
+/// [IndexedImage] on the GPU. The palette lookup happens in the shader,
+/// so swapping palettes is a 256 pixels texture update.
+///
+/// ```ignore
+/// let sprite = IndexedTexture::new(&IndexedImage::from_image(&image).unwrap())?;
+/// sprite.set_palette(&RED_TEAM);
+/// sprite.draw(x, y, WHITE, DrawTextureParams::default());
+/// ```
+///
+/// The palette shader is GLSL only, `new` returns an error on Metal.
+#[derive(Clone, Debug)]
+pub struct IndexedTexture {
+    indices: Texture2D,
+    palette: Texture2D,
+    material: Material,
+}
+
+impl IndexedTexture {
+    pub fn new(image: &IndexedImage) -> Result<IndexedTexture, Error> {
+        let bytes = image
+            .indices
+            .iter()
+            .flat_map(|index| [*index, 0, 0, 255])
+            .collect::<Vec<_>>();
+        // kept out of the texture atlas, a filtered index would be a wrong color
+        let indices = Texture2D::from_rgba8_unbatched(image.width, image.height, &bytes);
+        indices.set_filter(FilterMode::Nearest);
+
+        let palette = palette_image(&image.palette);
+        let palette =
+            Texture2D::from_rgba8_unbatched(palette.width, palette.height, &palette.bytes);
+        palette.set_filter(FilterMode::Nearest);
+
+        let material = indexed_material()?;
+
+        Ok(IndexedTexture {
+            indices,
+            palette,
+            material,
+        })
+    }
+
+    pub fn set_palette(&self, palette: &[Color; 256]) {
+        self.palette.update(&palette_image(palette));
+    }
+
+    pub fn width(&self) -> f32 {
+        self.indices.width()
+    }
+
+    pub fn height(&self) -> f32 {
+        self.indices.height()
+    }
+
+    /// Same as [draw_texture_ex], with the palette applied. `color` tints the palette colors.
+    pub fn draw(&self, x: f32, y: f32, color: Color, params: DrawTextureParams) {
+        // the material is shared and its textures are read when the batch is rendered,
+        // so whatever was drawn with the previous palette goes out first
+        unsafe { get_internal_gl() }.flush();
+        self.material.set_texture("Palette", self.palette.clone());
+
+        let previous_pipeline = get_context().gl.get_active_pipeline();
+        gl_use_material(&self.material);
+        draw_texture_ex(&self.indices, x, y, color, params);
+        get_context().gl.pipeline(previous_pipeline);
+    }
+}
+
+/// Shared by all the indexed textures, loaded with the first one.
+fn indexed_material() -> Result<Material, Error> {
+    if let Some(material) = &get_context().indexed_material {
+        return Ok(material.clone());
+    }
+
+    let material = load_material(
+        crate::ShaderSource::Glsl {
+            vertex: VERTEX,
+            fragment: FRAGMENT,
+        },
+        MaterialParams {
+            pipeline_params: PipelineParams {
+                color_blend: Some(BlendState::new(
+                    Equation::Add,
+                    BlendFactor::Value(BlendValue::SourceAlpha),
+                    BlendFactor::OneMinusValue(BlendValue::SourceAlpha),
+                )),
+                ..Default::default()
+            },
+            textures: vec!["Palette".to_string()],
+            ..Default::default()
+        },
+    )?;
+    get_context().indexed_material = Some(material.clone());
+
+    Ok(material)
+}
+
+fn palette_image(palette: &[Color; 256]) -> Image {
+    let mut image = Image::gen_image_color(256, 1, Color::new(0., 0., 0., 0.));
+    image.update(palette);
+    image
+}
+
+const VERTEX: &str = r#"#version 100
+attribute vec3 position;
+attribute vec2 texcoord;
+attribute vec4 color0;
+
+varying lowp vec2 uv;
+varying lowp vec4 color;
+
+uniform mat4 Model;
+uniform mat4 Projection;
+
+void main() {
+    gl_Position = Projection * Model * vec4(position, 1);
+    color = color0 / 255.0;
+    uv = texcoord;
+}
+"#;
+
+const FRAGMENT: &str = r#"#version 100
+varying lowp vec4 color;
+varying lowp vec2 uv;
+
+uniform sampler2D Texture;
+uniform sampler2D Palette;
+
+void main() {
+    mediump float index = texture2D(Texture, uv).r * 255.0;
+    gl_FragColor = texture2D(Palette, vec2((index + 0.5) / 256.0, 0.5)) * color;
+}
+"#;
+
+#[test]
+fn indexed_image_round_trips_through_rgba() {
+    let mut palette = [Color::new(0., 0., 0., 0.); 256];
+    palette[1] = Color::from_rgba(255, 0, 0, 255);
+    palette[7] = Color::from_rgba(0, 128, 255, 255);
+
+    let mut indexed = IndexedImage::new(3, 2, palette);
+    indexed.set_index(1, 0, 1);
+    indexed.set_index(2, 1, 7);
+
+    let rgba = indexed.to_rgba();
+    assert_eq!(rgba.get_image_data()[0], [0, 0, 0, 0]);
+    assert_eq!(rgba.get_image_data()[1], [255, 0, 0, 255]);
+    assert_eq!(rgba.get_image_data()[5], [0, 128, 255, 255]);
+
+    let back = IndexedImage::from_image(&rgba).unwrap();
+    assert_eq!(back.to_rgba().bytes, rgba.bytes);
+    assert_eq!(back.get_index(0, 0), 0);
+    assert_ne!(back.get_index(1, 0), back.get_index(2, 1));
+
+    let too_many_colors = Image {
+        bytes: (0..300u16)
+            .flat_map(|i| [i as u8, (i >> 8) as u8, 0, 255])
+            .collect(),
+        width: 300,
+        height: 1,
+    };
+    assert!(IndexedImage::from_image(&too_many_colors).is_none());
+}
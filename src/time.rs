@@ -14,6 +14,12 @@ pub fn get_fps() -> i32 {
     (1. / context.frame_time) as i32
 }
 
+/// Returns FPS averaged over the last 60 frames.
+/// Less jumpy than `get_fps`, better for on-screen counters.
+pub fn get_average_fps() -> f32 {
+    1. / get_average_frame_time()
+}
+
 /// Returns duration in seconds of the last frame drawn
 pub fn get_frame_time() -> f32 {
     let context = get_context();
@@ -25,6 +31,18 @@ pub fn get_frame_time() -> f32 {
     }
 }
 
+/// Returns duration in seconds of the last frame drawn, averaged over the last 60 frames.
+pub fn get_average_frame_time() -> f32 {
+    let context = get_context();
+
+    if context.frame_time_history.is_empty() {
+        return context.frame_time as f32;
+    }
+
+    let sum: f64 = context.frame_time_history.iter().sum();
+    (sum / context.frame_time_history.len() as f64) as f32
+}
+
 /// Returns elapsed wall-clock time in seconds since start
 ///
 /// Note that as real world time progresses during computation,
@@ -14,6 +14,7 @@
 
 pub mod canvas;
 mod clipboard;
+mod drag_payload;
 #[macro_use]
 mod hash;
 mod input_handler;
@@ -130,6 +131,8 @@ pub(crate) struct Window {
     pub childs: Vec<Id>,
     pub want_close: bool,
     pub force_focus: bool,
+    // content size of the last frame the window was drawn in, kept while it is hidden
+    pub measured_content_size: Option<Vec2>,
 
     margin: f32,
     window_margin: RectOffset,
@@ -173,6 +176,7 @@ impl Window {
             want_close: false,
             movable,
             force_focus,
+            measured_content_size: None,
         }
     }
 
@@ -356,6 +360,7 @@ pub struct Ui {
     dragging: Option<(Id, DragState)>,
     drag_hovered: Option<Id>,
     drag_hovered_previous_frame: Option<Id>,
+    drag_payload: drag_payload::DragPayload,
     active_window: Option<Id>,
     hovered_window: Id,
     in_modal: bool,
@@ -700,6 +705,7 @@ impl Ui {
             child_window_stack: vec![],
             drag_hovered: None,
             drag_hovered_previous_frame: None,
+            drag_payload: Default::default(),
             storage_u32: HashMap::default(),
             storage_any: AnyStorage::default(),
             atlas,
@@ -824,6 +830,21 @@ impl Ui {
         }
     }
 
+    /// Size of the window hugging what was laid out in it during the last frame,
+    /// no bigger than `max_size`. `None` until the window was drawn at least once,
+    /// after that a hidden window keeps the size it was last drawn with.
+    pub(crate) fn fitted_window_size(&self, id: Id, max_size: Vec2) -> Option<Vec2> {
+        let window = self.windows.get(&id)?;
+        let content = window.measured_content_size?;
+        let margin = window.window_margin;
+
+        let size = Vec2::new(
+            margin.left + content.x + window.margin + margin.right,
+            window.title_height + margin.top + content.y + window.margin + margin.bottom,
+        );
+        Some(size.min(max_size))
+    }
+
     pub(crate) fn begin_modal(&mut self, id: Id, position: Vec2, size: Vec2) -> WindowContext {
         self.input.window_active = true;
         self.in_modal = true;
@@ -1002,6 +1023,39 @@ impl Ui {
         self.dragging.is_some()
     }
 
+    /// Attach `payload` to the draggable group `id` while it is being dragged.
+    /// Call it every frame, right after the group.
+    ///
+    /// ```skip
+    /// for (slot, item) in inventory.iter().enumerate() {
+    ///     let id = hash!("slot", slot);
+    ///     Group::new(id, vec2(40., 40.)).draggable(true).ui(ui, |ui| ui.label(None, item));
+    ///     ui.drag_source(id, slot);
+    ///     if let Some(from) = ui.drop_target::<usize>(id) {
+    ///         inventory.swap(from, slot);
+    ///     }
+    /// }
+    /// ```
+    pub fn drag_source<T: std::any::Any>(&mut self, id: Id, payload: T) {
+        if let Some((dragging, DragState::Dragging(_))) = self.dragging {
+            if dragging == id {
+                self.drag_payload.set(id, Box::new(payload));
+            }
+        }
+    }
+
+    /// The payload of a [Ui::drag_source] dropped on the group `id` this frame.
+    /// Only draggable or hoverable groups can be dropped on.
+    pub fn drop_target<T: std::any::Any>(&mut self, id: Id) -> Option<T> {
+        let dropped_on = if self.input.is_mouse_down {
+            None
+        } else {
+            self.drag_hovered_previous_frame
+        };
+
+        self.drag_payload.take(id, dropped_on)
+    }
+
     pub fn close_current_window(&mut self) {
         let mut context = self.get_active_window_context();
         context.close();
@@ -1065,6 +1119,7 @@ impl Ui {
 
         self.drag_hovered_previous_frame = self.drag_hovered;
         self.drag_hovered = None;
+        self.drag_payload.new_frame(self.dragging.is_some());
         self.input.reset();
         self.input.window_active = self.hovered_window == 0;
 
@@ -1075,6 +1130,9 @@ impl Ui {
         for (_, window) in &mut self.windows {
             window.painter.clear();
             window.cursor.reset();
+            if window.active {
+                window.measured_content_size = Some(window.cursor.content_size_previous_frame);
+            }
             window.was_active = window.active;
             window.active = false;
             window.childs.clear();
@@ -51,6 +51,9 @@ pub struct Cursor {
     pub margin: f32,
     pub next_same_line: Option<f32>,
     pub max_row_y: f32,
+    /// Bottom right corner of everything laid out so far, relative to the area
+    pub content_size: Vec2,
+    pub content_size_previous_frame: Vec2,
 }
 
 impl Cursor {
@@ -74,6 +77,8 @@ impl Cursor {
             area,
             next_same_line: None,
             max_row_y: 0.,
+            content_size: Vec2::new(0., 0.),
+            content_size_previous_frame: Vec2::new(0., 0.),
         }
     }
 
@@ -82,6 +87,8 @@ impl Cursor {
         self.y = self.start_y;
         self.max_row_y = 0.;
         self.ident = 0.;
+        self.content_size_previous_frame = self.content_size;
+        self.content_size = Vec2::new(0., 0.);
         self.scroll.inner_rect_previous_frame = self.scroll.inner_rect;
         self.scroll.inner_rect = Rect::new(0., 0., self.area.w, self.area.h);
     }
@@ -134,6 +141,7 @@ impl Cursor {
             .scroll
             .inner_rect
             .combine_with(Rect::new(res.x, res.y, size.x, size.y));
+        self.content_size = self.content_size.max(res + size);
 
         res + Vec2::new(self.area.x, self.area.y) + self.scroll.scroll + Vec2::new(self.ident, 0.)
     }
@@ -0,0 +1,67 @@
+//! Typed values carried by a dragged group, see `Ui::drag_source` and `Ui::drop_target`.
+
+use super::Id;
+
+use std::any::Any;
+
+#[derive(Default)]
+pub(crate) struct DragPayload {
+    source: Option<Id>,
+    payload: Option<Box<dyn Any>>,
+}
+
+impl DragPayload {
+    pub(crate) fn set(&mut self, source: Id, payload: Box<dyn Any>) {
+        self.source = Some(source);
+        self.payload = Some(payload);
+    }
+
+    /// The payload, if it was dropped on `target` and has the right type.
+    pub(crate) fn take<T: Any>(&mut self, target: Id, dropped_on: Option<Id>) -> Option<T> {
+        if dropped_on != Some(target) || self.source == Some(target) {
+            return None;
+        }
+
+        match self.payload.take()?.downcast::<T>() {
+            Ok(payload) => {
+                self.source = None;
+                Some(*payload)
+            }
+            Err(payload) => {
+                self.payload = Some(payload);
+                None
+            }
+        }
+    }
+
+    /// Forget a payload nobody picked up on the frame it was dropped.
+    pub(crate) fn new_frame(&mut self, dragging: bool) {
+        if !dragging {
+            self.source = None;
+            self.payload = None;
+        }
+    }
+}
+
+#[test]
+fn payload_moves_from_source_to_target() {
+    let (source, target) = (1, 2);
+    let mut drag = DragPayload::default();
+
+    drag.new_frame(true);
+    drag.set(source, Box::new(42i32));
+    // still held over the target
+    assert_eq!(drag.take::<i32>(target, None), None);
+
+    // released over the target
+    drag.new_frame(true);
+    assert_eq!(drag.take::<u8>(target, Some(target)), None);
+    assert_eq!(drag.take::<i32>(source, Some(source)), None);
+    assert_eq!(drag.take::<i32>(target, Some(target)), Some(42));
+    assert_eq!(drag.take::<i32>(target, Some(target)), None);
+
+    // released over nothing
+    drag.set(source, Box::new(7i32));
+    drag.new_frame(false);
+    assert_eq!(drag.take::<i32>(target, Some(target)), None);
+}
@@ -4,8 +4,10 @@ mod combobox;
 mod drag;
 mod editbox;
 mod group;
+mod image_button;
 mod input;
 mod label;
+mod list_box;
 mod popup;
 mod separator;
 mod slider;
@@ -19,8 +21,10 @@ pub use checkbox::Checkbox;
 pub use combobox::ComboBox;
 pub use editbox::Editbox;
 pub use group::{Group, GroupToken};
+pub use image_button::ImageButton;
 pub use input::InputText;
 pub use label::Label;
+pub use list_box::ListBox;
 pub use popup::Popup;
 pub use slider::Slider;
 pub use tabbar::Tabbar;
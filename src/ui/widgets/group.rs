@@ -12,6 +12,7 @@ pub struct Group {
     draggable: bool,
     highlight: bool,
     hoverable: bool,
+    auto_size: bool,
 }
 
 impl Group {
@@ -24,6 +25,7 @@ impl Group {
             draggable: false,
             highlight: false,
             hoverable: false,
+            auto_size: false,
         }
     }
 
@@ -50,6 +52,11 @@ impl Group {
         Group { highlight, ..self }
     }
 
+    /// Shrink the group to hug its content, see [Window::auto_size](super::Window::auto_size).
+    pub const fn auto_size(self, auto_size: bool) -> Group {
+        Group { auto_size, ..self }
+    }
+
     pub fn ui<F: FnOnce(&mut Ui)>(self, ui: &mut Ui, f: F) -> Drag {
         let token = self.begin(ui);
         f(ui);
@@ -59,6 +66,12 @@ impl Group {
     pub fn begin(self, ui: &mut Ui) -> GroupToken {
         let mut drag = Drag::No;
 
+        let size = if self.auto_size {
+            ui.fitted_window_size(self.id, self.size)
+                .unwrap_or(self.size)
+        } else {
+            self.size
+        };
         let parent = ui.get_active_window_context();
 
         let parent_rect = parent.window.content_rect();
@@ -68,11 +81,11 @@ impl Group {
         let pos = parent
             .window
             .cursor
-            .fit(self.size, self.position.map_or(self.layout, Layout::Free));
-        let rect = Rect::new(pos.x, pos.y, self.size.x, self.size.y);
+            .fit(size, self.position.map_or(self.layout, Layout::Free));
+        let rect = Rect::new(pos.x, pos.y, size.x, size.y);
         let parent_id = Some(parent.window.id);
 
-        let mut context = ui.begin_window(self.id, parent_id, pos, self.size, false, true);
+        let mut context = ui.begin_window(self.id, parent_id, pos, size, false, true);
 
         let hovered =
             (self.hoverable || self.draggable) && rect.contains(context.input.mouse_position);
@@ -137,7 +150,7 @@ impl Group {
             draggable: self.draggable,
             drag,
             pos,
-            size: self.size,
+            size,
         }
     }
 }
@@ -0,0 +1,136 @@
+use crate::{
+    math::{Rect, Vec2},
+    texture::Texture2D,
+    ui::{ElementState, Id, Layout, Ui},
+};
+
+/// Button drawn with a texture per state, for toolbars and inventory grids.
+///
+/// `Ui::last_item_hovered` works right after it, for tooltips.
+/// The pressed texture is kept per `id`: it shows only while the press that
+/// started on this button is held over it.
+pub struct ImageButton {
+    id: Id,
+    position: Option<Vec2>,
+    size: Vec2,
+    texture: Texture2D,
+    hovered: Option<Texture2D>,
+    pressed: Option<Texture2D>,
+}
+
+impl ImageButton {
+    pub fn new(id: Id, texture: Texture2D) -> ImageButton {
+        ImageButton {
+            id,
+            position: None,
+            size: texture.size(),
+            texture,
+            hovered: None,
+            pressed: None,
+        }
+    }
+
+    pub fn position<P: Into<Option<Vec2>>>(self, position: P) -> Self {
+        let position = position.into();
+
+        ImageButton { position, ..self }
+    }
+
+    /// Defaults to the size of the texture.
+    pub fn size(self, size: Vec2) -> Self {
+        ImageButton { size, ..self }
+    }
+
+    /// Drawn while the mouse is over the button, instead of the normal texture.
+    pub fn hovered(self, texture: Texture2D) -> Self {
+        ImageButton {
+            hovered: Some(texture),
+            ..self
+        }
+    }
+
+    /// Drawn while the button is held down.
+    pub fn pressed(self, texture: Texture2D) -> Self {
+        ImageButton {
+            pressed: Some(texture),
+            ..self
+        }
+    }
+
+    /// Returns true on the frame the button was pressed, same as [Button](super::Button).
+    pub fn ui(self, ui: &mut Ui) -> bool {
+        let mut context = ui.get_active_window_context();
+
+        let pos = context.window.cursor.fit(
+            self.size,
+            self.position.map_or(Layout::Vertical, Layout::Free),
+        );
+        let rect = Rect::new(pos.x, pos.y, self.size.x, self.size.y);
+        let (hovered, clicked) = context.register_click_intention(rect);
+
+        let held = context.storage_any.get_or_default::<bool>(self.id);
+        if clicked {
+            *held = true;
+        }
+        if !context.input.is_mouse_down {
+            *held = false;
+        }
+
+        let state = ElementState {
+            focused: context.focused,
+            hovered,
+            clicked: *held && hovered,
+            selected: false,
+        };
+        let texture = state_texture(
+            &self.texture,
+            self.hovered.as_ref(),
+            self.pressed.as_ref(),
+            state,
+        );
+        context.window.painter.draw_raw_texture(rect, texture);
+
+        clicked
+    }
+}
+
+/// Same fallbacks as `Style::background_sprite`.
+fn state_texture<T>(normal: T, hovered: Option<T>, pressed: Option<T>, state: ElementState) -> T {
+    match (pressed, hovered) {
+        (Some(pressed), _) if state.clicked => pressed,
+        (_, Some(hovered)) if state.hovered => hovered,
+        _ => normal,
+    }
+}
+
+impl Ui {
+    pub fn image_button(&mut self, id: Id, texture: Texture2D) -> bool {
+        ImageButton::new(id, texture).ui(self)
+    }
+}
+
+#[test]
+fn pressed_texture_while_mouse_is_down() {
+    let pick = |hovered, clicked| {
+        let state = ElementState {
+            hovered,
+            clicked,
+            ..Default::default()
+        };
+        state_texture("normal", Some("hovered"), Some("pressed"), state)
+    };
+
+    assert_eq!(pick(true, true), "pressed");
+    assert_eq!(pick(true, false), "hovered");
+    assert_eq!(pick(false, false), "normal");
+
+    let state = ElementState {
+        hovered: true,
+        clicked: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        state_texture("normal", Some("hovered"), None, state),
+        "hovered"
+    );
+}
@@ -0,0 +1,88 @@
+use crate::{
+    math::{vec2, Vec2},
+    ui::{
+        widgets::{Button, Group},
+        Id, Layout, Ui, UiContent,
+    },
+};
+
+/// Scrollable list of selectable rows, for file pickers, entity lists and inventories.
+///
+/// ```ignore
+/// if ListBox::new(hash!(), vec2(200., 300.), &files).ui(&mut root_ui(), &mut selected) {
+///     open(&files[selected.unwrap()]);
+/// }
+/// ```
+pub struct ListBox<'a, T> {
+    id: Id,
+    size: Vec2,
+    position: Option<Vec2>,
+    items: &'a [T],
+}
+
+impl<'a, T: AsRef<str>> ListBox<'a, T> {
+    pub const fn new(id: Id, size: Vec2, items: &'a [T]) -> ListBox<'a, T> {
+        ListBox {
+            id,
+            size,
+            position: None,
+            items,
+        }
+    }
+
+    pub const fn position(self, position: Vec2) -> Self {
+        ListBox {
+            position: Some(position),
+            ..self
+        }
+    }
+
+    /// Returns true when a click selected a different row this frame.
+    pub fn ui(self, ui: &mut Ui, selected: &mut Option<usize>) -> bool {
+        let mut group = Group::new(self.id, self.size).layout(Layout::Vertical);
+        if let Some(position) = self.position {
+            group = group.position(position);
+        }
+        // the group brings the scroll area along
+        let token = group.begin(ui);
+
+        let mut changed = false;
+        for (i, item) in self.items.iter().enumerate() {
+            let context = ui.get_active_window_context();
+            let width = context.window.content_rect().w - context.window.cursor.margin * 2.;
+            let height = context
+                .window
+                .painter
+                .content_with_margins_size(
+                    &context.style.button_style,
+                    &UiContent::Label(item.as_ref().into()),
+                )
+                .y;
+
+            let clicked = Button::new(item.as_ref())
+                .size(vec2(width, height))
+                .selected(*selected == Some(i))
+                .ui(ui);
+            if clicked && *selected != Some(i) {
+                *selected = Some(i);
+                changed = true;
+            }
+        }
+
+        token.end(ui);
+
+        changed
+    }
+}
+
+impl Ui {
+    pub fn list_box<T: AsRef<str>>(
+        &mut self,
+        id: Id,
+        size: Vec2,
+        items: &[T],
+        selected: &mut Option<usize>,
+    ) -> bool {
+        ListBox::new(id, size, items).ui(self, selected)
+    }
+}
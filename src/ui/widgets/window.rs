@@ -11,6 +11,7 @@ pub struct Window {
     close_button: bool,
     movable: bool,
     titlebar: bool,
+    auto_size: bool,
     label: Option<String>,
 }
 
@@ -23,6 +24,7 @@ impl Window {
             close_button: false,
             movable: true,
             titlebar: true,
+            auto_size: false,
             label: None,
         }
     }
@@ -58,6 +60,14 @@ impl Window {
         Window { titlebar, ..self }
     }
 
+    /// Shrink the window to hug its content, for tooltips and context menus.
+    ///
+    /// The size given to [Window::new] is the largest the window can get and is
+    /// also used on the very first frame, before the content was measured.
+    pub fn auto_size(self, auto_size: bool) -> Window {
+        Window { auto_size, ..self }
+    }
+
     pub fn ui<F: FnOnce(&mut Ui)>(self, ui: &mut Ui, f: F) -> bool {
         let token = self.begin(ui);
         f(ui);
@@ -65,11 +75,17 @@ impl Window {
     }
 
     pub fn begin(self, ui: &mut Ui) -> WindowToken {
+        let size = if self.auto_size {
+            ui.fitted_window_size(self.id, self.size)
+                .unwrap_or(self.size)
+        } else {
+            self.size
+        };
         let context = ui.begin_window(
             self.id,
             None,
             self.position,
-            self.size,
+            size,
             self.titlebar,
             self.movable,
         );
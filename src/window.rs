@@ -24,6 +24,59 @@ pub fn clear_background(color: Color) {
     context.gl.clear(get_quad_context(), color);
 }
 
+/// Buffers cleared by [clear_background_ex], `None` leaves a buffer untouched.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ClearParams {
+    pub color: Option<Color>,
+    pub depth: Option<f32>,
+    pub stencil: Option<i32>,
+}
+
+/// Clear some of the buffers of the current render target.
+/// When the color is kept, what was drawn so far is kept as well:
+/// ```ignore
+/// draw_skybox();
+/// clear_background_ex(ClearParams {
+///     depth: Some(1.),
+///     ..Default::default()
+/// });
+/// draw_scene();
+/// ```
+pub fn clear_background_ex(params: ClearParams) {
+    let context = get_context();
+
+    if params.color.is_none() {
+        context.perform_render_passes();
+    }
+    context.gl.clear_ex(
+        get_quad_context(),
+        params.color,
+        params.depth,
+        params.stencil,
+    );
+}
+
+/// Draw `content` clipped to the shapes drawn in `mask`, for non-rectangular
+/// clipping and transition wipes. Everything drawn in `mask` only shapes the
+/// clip area and is not visible itself.
+///
+/// ```ignore
+/// with_stencil_mask(
+///     || draw_circle(x, y, reveal_radius, WHITE),
+///     || draw_texture(&level, 0., 0., WHITE),
+/// );
+/// ```
+///
+/// Draws with custom materials ignore the mask. Needs a stencil buffer on the
+/// screen, so it does nothing useful inside render targets or on WebGL.
+pub fn with_stencil_mask(mask: impl FnOnce(), content: impl FnOnce()) {
+    get_context().gl.begin_stencil_mask();
+    mask();
+    get_context().gl.begin_stencil_masked();
+    content();
+    get_context().gl.end_stencil();
+}
+
 #[doc(hidden)]
 pub fn gl_set_drawcall_buffer_capacity(max_vertices: usize, max_indices: usize) {
     let context = get_context();
@@ -54,21 +107,51 @@ pub unsafe fn get_internal_gl<'a>() -> InternalGlContext<'a> {
     }
 }
 
+/// Width of the window in logical pixels.
+///
+/// Everything in macroquad works in logical pixels: the default camera,
+/// `mouse_position`, UI. On a high-DPI display one logical pixel is
+/// `screen_dpi_scale()` physical pixels, see [screen_size_physical].
 pub fn screen_width() -> f32 {
     let context = get_context();
     context.screen_width / miniquad::window::dpi_scale()
 }
 
+/// Height of the window in logical pixels, see [screen_width].
 pub fn screen_height() -> f32 {
     let context = get_context();
 
     context.screen_height / miniquad::window::dpi_scale()
 }
 
+/// Returns the new screen size, the same as `(screen_width(), screen_height())`,
+/// if the window was resized since the last frame.
+///
+/// Useful to re-create screen-sized render targets only when needed.
+pub fn screen_resized() -> Option<(f32, f32)> {
+    if get_context().screen_resized {
+        Some((screen_width(), screen_height()))
+    } else {
+        None
+    }
+}
+
+/// Physical pixels per logical pixel.
+///
+/// Stays 1.0 unless `Conf::high_dpi` is set and the display is high-DPI.
 pub fn screen_dpi_scale() -> f32 {
     miniquad::window::dpi_scale()
 }
 
+/// Size of the framebuffer in physical pixels.
+///
+/// Render targets meant to cover the screen pixel for pixel should be this big,
+/// a `screen_width()` x `screen_height()` one gets upscaled and looks blurry.
+pub fn screen_size_physical() -> (u32, u32) {
+    let context = get_context();
+    (context.screen_width as u32, context.screen_height as u32)
+}
+
 /// Request the window size to be the given value. This takes DPI into account.
 ///
 /// Note that the OS might decide to give a different size. Additionally, the size in macroquad won't be updated until the next `next_frame().await`.
@@ -83,10 +166,95 @@ pub fn request_new_screen_size(width: f32, height: f32) {
 }
 
 /// Toggle whether the window is fullscreen.
+///
+/// On web browsers only allow going fullscreen from a user gesture, so this should be
+/// called in response to a click or a key press, otherwise the request may be ignored.
 pub fn set_fullscreen(fullscreen: bool) {
     miniquad::window::set_fullscreen(fullscreen);
 }
 
+/// Text currently in the system clipboard, if any.
+///
+/// On web the browser only gives clipboard access while handling a paste,
+/// so this may return `None` outside of a ctrl+v key press.
+pub fn clipboard_get() -> Option<String> {
+    miniquad::window::clipboard_get()
+}
+
+/// Put text into the system clipboard.
+pub fn clipboard_set(data: &str) {
+    miniquad::window::clipboard_set(data);
+}
+
+/// Fixed timestep accumulator, for updates that need the same `dt` every step,
+/// like physics.
+///
+/// ```ignore
+/// let mut timestep = FixedTimestep::new(1.0 / 60.0);
+/// loop {
+///     for dt in timestep.tick(get_frame_time()) {
+///         world.update(dt);
+///     }
+///     world.draw(timestep.alpha());
+///     next_frame().await;
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct FixedTimestep {
+    /// Duration of one step, in seconds.
+    pub dt: f32,
+    /// Steps to run at most per tick. After a long stall the remaining
+    /// time is dropped instead of trying to catch up, otherwise slow updates
+    /// would make the next frame even slower.
+    pub max_steps: u32,
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    pub fn new(dt: f32) -> FixedTimestep {
+        assert!(dt > 0.0, "Fixed timestep should be positive");
+
+        FixedTimestep {
+            dt,
+            max_steps: 8,
+            accumulator: 0.0,
+        }
+    }
+
+    /// Add the frame time and return an iterator with `dt` for each step to run this frame.
+    pub fn tick(&mut self, frame_time: f32) -> impl Iterator<Item = f32> {
+        self.accumulator += frame_time.max(0.0);
+
+        let steps = ((self.accumulator / self.dt) as u32).min(self.max_steps);
+        self.accumulator -= steps as f32 * self.dt;
+        if steps == self.max_steps {
+            self.accumulator = self.accumulator.min(self.dt);
+        }
+
+        let dt = self.dt;
+        (0..steps).map(move |_| dt)
+    }
+
+    /// How far, from 0 to 1, the time is between the last step and the next one.
+    /// Useful to interpolate rendering between the previous and current state.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.dt).clamp(0.0, 1.0)
+    }
+}
+
+#[test]
+fn fixed_timestep_caps_catch_up() {
+    let mut timestep = FixedTimestep::new(0.25);
+
+    assert_eq!(timestep.tick(0.625).count(), 2);
+    assert_eq!(timestep.alpha(), 0.5);
+    assert_eq!(timestep.tick(0.125).count(), 1);
+
+    // a long stall only runs max_steps and forgets the rest
+    assert_eq!(timestep.tick(10.0).count(), 8);
+    assert!(timestep.tick(0.0).count() <= 1);
+}
+
 /// With `set_panic_handler` set to a handler code, macroquad will use
 /// `std::panic::catch_unwind` on user code to catch some panics.
 ///
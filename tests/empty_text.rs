@@ -0,0 +1,19 @@
+use macroquad::text::{draw_text_aligned, measure_text, HAlign, TextParams, VAlign};
+
+#[macroquad::test]
+async fn empty_text_has_no_size() {
+    let size = measure_text("", None, 32, 1.0);
+    assert_eq!((size.width, size.height, size.offset_y), (0., 0., 0.));
+
+    for halign in [HAlign::Left, HAlign::Center, HAlign::Right] {
+        for valign in [
+            VAlign::Top,
+            VAlign::Center,
+            VAlign::Baseline,
+            VAlign::Bottom,
+        ] {
+            let size = draw_text_aligned("", 100., 50., TextParams::default(), halign, valign);
+            assert_eq!((size.width, size.height, size.offset_y), (0., 0., 0.));
+        }
+    }
+}
@@ -28,9 +28,24 @@ pub struct Object {
 
     pub name: String,
 
+    pub shape: ObjectShape,
+
     pub properties: HashMap<String, String>,
 }
 
+/// Shape of an object, as drawn in Tiled.
+/// The `world_*` fields of the object hold its position and bounding box size.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ObjectShape {
+    Rect,
+    Ellipse,
+    Point,
+    /// Closed polygon, points are in pixels relative to the object position.
+    Polygon(Vec<Vec2>),
+    /// Open polyline, points are in pixels relative to the object position.
+    Polyline(Vec<Vec2>),
+}
+
 /// Flip operation application order:
 /// 1. flip diagonally
 /// 2. flip horizontally
@@ -91,6 +106,9 @@ impl TileSet {
 #[derive(Debug)]
 pub struct Map {
     pub layers: HashMap<String, Layer>,
+    /// Names of all the `layers`, bottom to top in the order Tiled draws them.
+    /// Layers inside a group layer are named "group/layer".
+    pub layer_order: Vec<String>,
     pub tilesets: HashMap<String, TileSet>,
 
     /// Deserialized json as is
@@ -180,40 +198,16 @@ impl Map {
     pub fn draw_tiles(&self, layer: &str, dest: Rect, source: impl Into<Option<Rect>>) {
         assert!(self.layers.contains_key(layer), "No such layer: {}", layer);
 
-        let source = source.into().unwrap_or(Rect::new(
-            0.,
-            0.,
-            self.raw_tiled_map.width as f32,
-            self.raw_tiled_map.height as f32,
-        ));
+        let source = source.into().unwrap_or(self.full_source());
         let layer = &self.layers[layer];
 
-        let spr_width = dest.w / source.w;
-        let spr_height = dest.h / source.h;
-
         let mut separated_by_ts: HashMap<&str, Vec<(&Tile, Rect)>> = HashMap::new();
 
-        for y in source.y as u32..source.y as u32 + source.h as u32 {
-            for x in source.x as u32..source.x as u32 + source.w as u32 {
-                if let Some(tile) = &layer
-                    .data
-                    .get((y * layer.width + x) as usize)
-                    .unwrap_or(&None)
-                {
-                    if !separated_by_ts.contains_key(tile.tileset.as_str()) {
-                        separated_by_ts.insert(&tile.tileset, vec![]);
-                    }
-
-                    let pos = vec2(
-                        (x - source.x as u32) as f32 / source.w * dest.w + dest.x,
-                        (y - source.y as u32) as f32 / source.h * dest.h + dest.y,
-                    );
-                    separated_by_ts
-                        .get_mut(tile.tileset.as_str())
-                        .unwrap()
-                        .push((&tile, Rect::new(pos.x, pos.y, spr_width, spr_height)));
-                }
-            }
+        for (tile, rect) in visible_tiles(layer, dest, source) {
+            separated_by_ts
+                .entry(tile.tileset.as_str())
+                .or_default()
+                .push((tile, rect));
         }
 
         for (tileset, tileset_layer) in &separated_by_ts {
@@ -232,15 +226,90 @@ impl Map {
         }
     }
 
+    /// Bake the same tiles `draw_tiles` would draw into a few meshes,
+    /// one per tileset (split further for really large areas).
+    ///
+    /// Drawing the result costs a handful of draw calls instead of one
+    /// `draw_texture_ex` per tile. The mesh is only valid for the given
+    /// `dest` and `source`, rebuild it when the visible region changes:
+    ///
+    /// ```ignore
+    /// if !mesh.is_built_for(dest, source) {
+    ///     mesh = map.build_mesh("main layer", dest, source);
+    /// }
+    /// mesh.draw();
+    /// ```
+    pub fn build_mesh(&self, layer: &str, dest: Rect, source: impl Into<Option<Rect>>) -> TileMesh {
+        assert!(self.layers.contains_key(layer), "No such layer: {}", layer);
+
+        let requested_source = source.into();
+        let source = requested_source.unwrap_or(self.full_source());
+        let layer = &self.layers[layer];
+
+        let mut meshes: HashMap<&str, Vec<Mesh>> = HashMap::new();
+
+        for (tile, rect) in visible_tiles(layer, dest, source) {
+            let tileset = &self.tilesets[&tile.tileset];
+            let tileset_meshes = meshes.entry(tile.tileset.as_str()).or_default();
+
+            let full = match tileset_meshes.last() {
+                Some(mesh) => mesh.vertices.len() >= TileMesh::TILES_PER_MESH * 4,
+                None => true,
+            };
+            if full {
+                tileset_meshes.push(Mesh {
+                    vertices: vec![],
+                    indices: vec![],
+                    texture: Some(tileset.texture.clone()),
+                });
+            }
+            let mesh = tileset_meshes.last_mut().unwrap();
+
+            let source = tileset.sprite_rect(tile.id);
+            let size = tileset.texture.size();
+            let uv = Rect::new(
+                (source.x - 1.0) / size.x,
+                (source.y - 1.0) / size.y,
+                (source.w + 2.0) / size.x,
+                (source.h + 2.0) / size.y,
+            );
+
+            let first = mesh.vertices.len() as u16;
+            mesh.indices
+                .extend([0, 1, 2, 0, 2, 3].iter().map(|i| i + first));
+            mesh.vertices.extend_from_slice(&tile_vertices(
+                uv,
+                rect,
+                TileFlippedParams {
+                    flip_x: tile.flip_x,
+                    flip_y: tile.flip_y,
+                    flip_d: tile.flip_d,
+                },
+            ));
+        }
+
+        TileMesh {
+            meshes: meshes.into_values().flatten().collect(),
+            dest,
+            source: requested_source,
+        }
+    }
+
+    fn full_source(&self) -> Rect {
+        Rect::new(
+            0.,
+            0.,
+            self.raw_tiled_map.width as f32,
+            self.raw_tiled_map.height as f32,
+        )
+    }
+
     pub fn draw_imglayer(&self, layer: &str, dest: Rect, source: Option<Rect>) {
         assert!(self.layers.contains_key(layer), "No such layer: {}", layer);
         let layer = &self.layers[layer];
         assert!(layer.image.is_some(), "No texture found.");
         let img_texture = layer.image.clone().unwrap();
-        let dest_width_frac =
-            img_texture.width() / (self.raw_tiled_map.width * self.raw_tiled_map.tilewidth) as f32;
-        let dest_height_frac =
-            img_texture.height() / (self.raw_tiled_map.height * self.raw_tiled_map.height) as f32;
+        let dest_size = imglayer_dest_size(&self.raw_tiled_map, img_texture.size(), dest);
 
         let source = source.unwrap_or(Rect::new(0., 0., img_texture.width(), img_texture.height()));
         draw_texture_ex(
@@ -254,7 +323,7 @@ impl Map {
                 a: layer.opacity,
             },
             DrawTextureParams {
-                dest_size: Some(vec2(dest.w * dest_width_frac, dest.h * dest_height_frac)),
+                dest_size: Some(dest_size),
                 source: Some(source),
                 ..Default::default()
             },
@@ -286,6 +355,210 @@ impl Map {
     }
 }
 
+/// Tiles of a layer baked by [Map::build_mesh].
+pub struct TileMesh {
+    meshes: Vec<Mesh>,
+    dest: Rect,
+    source: Option<Rect>,
+}
+
+impl TileMesh {
+    // default draw call capacity is 5000 indices, 6 indices per tile
+    const TILES_PER_MESH: usize = 800;
+
+    /// Whether this mesh was built for the given `dest` and `source`
+    /// or it needs to be rebuilt.
+    pub fn is_built_for(&self, dest: Rect, source: impl Into<Option<Rect>>) -> bool {
+        self.dest == dest && self.source == source.into()
+    }
+
+    pub fn draw(&self) {
+        for mesh in &self.meshes {
+            draw_mesh(mesh);
+        }
+    }
+}
+
+/// Tiles of the layer inside `source` (in tiles) with their place in `dest`.
+fn visible_tiles(layer: &Layer, dest: Rect, source: Rect) -> Vec<(&Tile, Rect)> {
+    let spr_width = dest.w / source.w;
+    let spr_height = dest.h / source.h;
+
+    let mut tiles = vec![];
+    for y in source.y as u32..source.y as u32 + source.h as u32 {
+        for x in source.x as u32..source.x as u32 + source.w as u32 {
+            if let Some(tile) = &layer
+                .data
+                .get((y * layer.width + x) as usize)
+                .unwrap_or(&None)
+            {
+                let pos = vec2(
+                    (x - source.x as u32) as f32 / source.w * dest.w + dest.x,
+                    (y - source.y as u32) as f32 / source.h * dest.h + dest.y,
+                );
+                tiles.push((tile, Rect::new(pos.x, pos.y, spr_width, spr_height)));
+            }
+        }
+    }
+    tiles
+}
+
+/// Size of an image layer when the whole map is drawn into `dest`,
+/// relative to the map it is as big as in Tiled.
+fn imglayer_dest_size(map: &tiled::Map, image_size: Vec2, dest: Rect) -> Vec2 {
+    let map_size = vec2(
+        (map.width * map.tilewidth) as f32,
+        (map.height * map.tileheight) as f32,
+    );
+    image_size / map_size * dest.size()
+}
+
+/// Same quad `spr_flip` draws with `draw_texture_ex`, `uv` is normalized.
+fn tile_vertices(uv: Rect, dest: Rect, flip: TileFlippedParams) -> [Vertex; 4] {
+    let (mut x, mut y, mut w, mut h) = (dest.x, dest.y, dest.w, dest.h);
+    if flip.flip_x {
+        x += w;
+        w = -w;
+    }
+    if flip.flip_y ^ flip.flip_d {
+        y += h;
+        h = -h;
+    }
+    let rotation = if flip.flip_d { -PI / 2.0 } else { 0.0 };
+
+    let pivot = vec2(x + w / 2., y + h / 2.);
+    let corners = [
+        vec2(x, y),
+        vec2(x + w, y),
+        vec2(x + w, y + h),
+        vec2(x, y + h),
+    ];
+    let uvs = [
+        vec2(uv.x, uv.y),
+        vec2(uv.x + uv.w, uv.y),
+        vec2(uv.x + uv.w, uv.y + uv.h),
+        vec2(uv.x, uv.y + uv.h),
+    ];
+
+    let rotation = Mat2::from_angle(rotation);
+    let mut vertices = [Vertex::new(0., 0., 0., 0., 0., WHITE); 4];
+    for (vertex, (corner, uv)) in vertices.iter_mut().zip(corners.iter().zip(&uvs)) {
+        let p = rotation * (*corner - pivot) + pivot;
+        *vertex = Vertex::new(p.x, p.y, 0., uv.x, uv.y, WHITE);
+    }
+    vertices
+}
+
+#[test]
+fn mesh_matches_draw_tiles_rects() {
+    let tile = |id, flip_d| {
+        Some(Tile {
+            id,
+            tileset: "tiles".to_string(),
+            attrs: String::new(),
+            flip_x: false,
+            flip_y: false,
+            flip_d,
+        })
+    };
+    let layer = Layer {
+        width: 3,
+        height: 2,
+        data: vec![
+            tile(0, false),
+            None,
+            tile(1, true),
+            None,
+            tile(2, false),
+            tile(3, false),
+        ],
+        ..Default::default()
+    };
+
+    let dest = Rect::new(10., 20., 90., 60.);
+    let tiles = visible_tiles(&layer, dest, Rect::new(0., 0., 3., 2.));
+    let rects = tiles.iter().map(|(_, rect)| *rect).collect::<Vec<_>>();
+    assert_eq!(
+        rects,
+        [
+            Rect::new(10., 20., 30., 30.),
+            Rect::new(70., 20., 30., 30.),
+            Rect::new(40., 50., 30., 30.),
+            Rect::new(70., 50., 30., 30.),
+        ]
+    );
+
+    // corners and uvs draw_texture_ex gives for the same dest and flips
+    let uv = Rect::new(0.25, 0.5, 0.25, 0.5);
+    let quad = |flip_x, flip_y, flip_d, dest| {
+        tile_vertices(
+            uv,
+            dest,
+            TileFlippedParams {
+                flip_x,
+                flip_y,
+                flip_d,
+            },
+        )
+        .map(|v| (v.position.xy(), v.uv))
+    };
+    let uvs = [
+        vec2(0.25, 0.5),
+        vec2(0.5, 0.5),
+        vec2(0.5, 1.0),
+        vec2(0.25, 1.0),
+    ];
+    let cases = [
+        // plain tile
+        (
+            quad(false, false, false, rects[0]),
+            [
+                vec2(10., 20.),
+                vec2(40., 20.),
+                vec2(40., 50.),
+                vec2(10., 50.),
+            ],
+        ),
+        // flip_x mirrors the corners around the vertical axis
+        (
+            quad(true, false, false, rects[0]),
+            [
+                vec2(40., 20.),
+                vec2(10., 20.),
+                vec2(10., 50.),
+                vec2(40., 50.),
+            ],
+        ),
+        // flip_d flips vertically then rotates by -90 degrees around the center
+        (
+            quad(false, false, true, rects[1]),
+            [
+                vec2(100., 50.),
+                vec2(100., 20.),
+                vec2(70., 20.),
+                vec2(70., 50.),
+            ],
+        ),
+    ];
+    for (vertices, corners) in cases {
+        for ((position, uv), (corner, expected_uv)) in vertices.iter().zip(corners.iter().zip(&uvs))
+        {
+            assert!(
+                position.abs_diff_eq(*corner, 0.001),
+                "{} != {}",
+                position,
+                corner
+            );
+            assert!(
+                uv.abs_diff_eq(*expected_uv, 0.001),
+                "{} != {}",
+                uv,
+                expected_uv
+            );
+        }
+    }
+}
+
 pub struct TilesIterator<'a> {
     rect: Rect,
     current: (u32, u32),
@@ -343,12 +616,8 @@ pub fn load_map(
     textures: &[(&str, Texture2D)],
     external_tilesets: &[(&str, &str)],
 ) -> Result<Map, error::Error> {
-    // Tiled reserves 4 high bits for flip flags
-    const TILE_FLIP_FLAGS: u32 = 0b11110000000000000000000000000000;
-
     let map: tiled::Map = DeJson::deserialize_json(data)?;
 
-    let mut layers = HashMap::new();
     let mut tilesets = HashMap::new();
     let mut map_tilesets = vec![];
 
@@ -388,11 +657,94 @@ pub fn load_map(
         map_tilesets.push(tileset);
     }
 
-    for layer in &map.layers {
-        if layers.contains_key(&layer.name) {
-            return Err(error::Error::NonUniqueLayerName {
-                layer: layer.name.clone(),
-            });
+    let mut layers = HashMap::new();
+    let mut layer_order = vec![];
+    load_layers(
+        &map.layers,
+        "",
+        &map,
+        &map_tilesets,
+        textures,
+        &mut layers,
+        &mut layer_order,
+    )?;
+
+    // Some external tilesets could be resolved, so we
+    // include the new "map_tilesets"
+    Ok(Map {
+        layers,
+        layer_order,
+        tilesets,
+        raw_tiled_map: tiled::Map {
+            tilesets: map_tilesets,
+            ..map
+        },
+    })
+}
+
+fn object_shape(object: &tiled::layer::Object) -> ObjectShape {
+    let points = |points: &[tiled::layer::PolyPoint]| {
+        points
+            .iter()
+            .map(|point| vec2(point.x, point.y))
+            .collect::<Vec<_>>()
+    };
+
+    if let Some(polygon) = &object.polygon {
+        ObjectShape::Polygon(points(polygon))
+    } else if let Some(polyline) = &object.polyline {
+        ObjectShape::Polyline(points(polyline))
+    } else if object.ellipse == Some(true) {
+        ObjectShape::Ellipse
+    } else if object.point == Some(true) {
+        ObjectShape::Point
+    } else {
+        ObjectShape::Rect
+    }
+}
+
+/// Convert `raw_layers` and append them to `layers` and `layer_order`.
+/// Group layers are flattened, their children are named "group/child".
+fn load_layers(
+    raw_layers: &[tiled::layer::Layer],
+    prefix: &str,
+    map: &tiled::Map,
+    map_tilesets: &[tiled::Tileset],
+    textures: &[(&str, Texture2D)],
+    layers: &mut HashMap<String, Layer>,
+    layer_order: &mut Vec<String>,
+) -> Result<(), error::Error> {
+    // Tiled reserves 4 high bits for flip flags
+    const TILE_FLIP_FLAGS: u32 = 0b11110000000000000000000000000000;
+
+    for layer in raw_layers {
+        let name = format!("{}{}", prefix, layer.name);
+        if layers.contains_key(&name) {
+            return Err(error::Error::NonUniqueLayerName { layer: name });
+        }
+
+        if layer.ty == "group" {
+            // group offset and opacity apply to all the children
+            let children = layer
+                .layers
+                .iter()
+                .map(|child| tiled::layer::Layer {
+                    opacity: child.opacity * layer.opacity,
+                    offsetx: Some(child.offsetx.unwrap_or(0) + layer.offsetx.unwrap_or(0)),
+                    offsety: Some(child.offsety.unwrap_or(0) + layer.offsety.unwrap_or(0)),
+                    ..child.clone()
+                })
+                .collect::<Vec<_>>();
+            load_layers(
+                &children,
+                &format!("{}/", name),
+                map,
+                map_tilesets,
+                textures,
+                layers,
+                layer_order,
+            )?;
+            continue;
         }
 
         let tile_width = map.tilewidth as f32;
@@ -412,6 +764,7 @@ pub fn load_map(
                 tile_w: (object.width / tile_width) as u32,
                 tile_h: (object.height / tile_height) as u32,
                 name: object.name.clone(),
+                shape: object_shape(object),
                 properties: object
                     .properties
                     .iter()
@@ -429,7 +782,7 @@ pub fn load_map(
         };
 
         layers.insert(
-            layer.name.clone(),
+            name.clone(),
             match layer.ty.as_str() {
                 "tilelayer" | "objectgroup" => Layer {
                     objects,
@@ -498,16 +851,83 @@ pub fn load_map(
                 }
             },
         );
+        layer_order.push(name);
     }
 
-    // Some external tilesets could be resolved, so we
-    // include the new "map_tilesets"
-    Ok(Map {
-        layers,
-        tilesets,
-        raw_tiled_map: tiled::Map {
-            tilesets: map_tilesets,
-            ..map
-        },
-    })
+    Ok(())
+}
+
+#[test]
+fn imglayer_scales_with_the_map() {
+    // 10x4 map of 16x8 tiles, 160x32 pixels
+    let map = tiled::Map {
+        width: 10,
+        height: 4,
+        tilewidth: 16,
+        tileheight: 8,
+        ..Default::default()
+    };
+
+    // drawn twice as big as in Tiled
+    let dest = Rect::new(0., 0., 320., 64.);
+    assert_eq!(
+        imglayer_dest_size(&map, vec2(80., 16.), dest),
+        vec2(160., 32.)
+    );
+}
+
+#[test]
+fn group_layers_are_flattened_in_order() {
+    let data = r#"{
+        "width": 2, "height": 1, "tilewidth": 8, "tileheight": 8,
+        "layers": [
+            {"name": "ground", "type": "tilelayer", "width": 2, "height": 1, "data": [0, 0]},
+            {"name": "props", "type": "group", "layers": [
+                {"name": "ground", "type": "tilelayer", "width": 2, "height": 1, "data": [0, 0]},
+                {"name": "spawns", "type": "objectgroup", "objects": [{"name": "player", "x": 8, "y": 0}]}
+            ]},
+            {"name": "sky", "type": "tilelayer", "width": 2, "height": 1, "data": [0, 0]}
+        ]
+    }"#;
+
+    let map = load_map(data, &[], &[]).unwrap();
+    assert_eq!(
+        map.layer_order,
+        ["ground", "props/ground", "props/spawns", "sky"]
+    );
+    assert!(map.contains_layer("props/ground"));
+    assert_eq!(map.layers["props/spawns"].objects[0].name, "player");
+}
+
+#[test]
+fn object_shapes() {
+    let data = r#"{
+        "width": 4, "height": 4, "tilewidth": 8, "tileheight": 8,
+        "layers": [
+            {"name": "collision", "type": "objectgroup", "objects": [
+                {"name": "ramp", "x": 8, "y": 16, "polygon": [{"x": 0, "y": 0}, {"x": 16, "y": -8}, {"x": 16, "y": 0}]},
+                {"name": "rope", "x": 0, "y": 0, "polyline": [{"x": 0, "y": 0}, {"x": 4, "y": 4}]},
+                {"name": "spawn", "x": 4, "y": 4, "point": true},
+                {"name": "pit", "x": 0, "y": 24, "width": 8, "height": 8, "ellipse": true},
+                {"name": "wall", "x": 24, "y": 0, "width": 8, "height": 32}
+            ]}
+        ]
+    }"#;
+
+    let map = load_map(data, &[], &[]).unwrap();
+    let shapes = map.layers["collision"]
+        .objects
+        .iter()
+        .map(|object| object.shape.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        shapes,
+        [
+            ObjectShape::Polygon(vec![vec2(0., 0.), vec2(16., -8.), vec2(16., 0.)]),
+            ObjectShape::Polyline(vec![vec2(0., 0.), vec2(4., 4.)]),
+            ObjectShape::Point,
+            ObjectShape::Ellipse,
+            ObjectShape::Rect,
+        ]
+    );
 }
@@ -50,6 +50,9 @@ pub struct Layer {
 
     /// for type = "imagelayer"
     pub image: Option<String>,
+
+    /// for type = "group"
+    pub layers: Vec<Layer>,
 }
 
 #[derive(Clone, Debug, Default, DeJson)]
@@ -62,7 +65,9 @@ pub struct Object {
     pub ty: String,
     pub gid: Option<u32>,
     pub ellipse: Option<bool>,
+    pub point: Option<bool>,
     pub polygon: Option<Vec<PolyPoint>>,
+    pub polyline: Option<Vec<PolyPoint>>,
 
     pub properties: Vec<Property>,
     pub rotation: f32,